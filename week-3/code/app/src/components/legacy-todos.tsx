@@ -0,0 +1,116 @@
+"use client";
+
+import useAnchorProvider from "@/hooks/use-anchor-provider";
+import TodoProgram from "@/lib/todo-program";
+import {
+  Button,
+  Center,
+  Flex,
+  List,
+  ListItem,
+  Spinner,
+  Text,
+  useToast,
+} from "@chakra-ui/react";
+import { IdlAccounts } from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
+import { IDL } from "../../../target/types/todo_app";
+import TodoItem from "./todo-item";
+
+// Todos created directly under the profile, before lists existed
+export default function LegacyTodos({
+  profile,
+}: {
+  profile: IdlAccounts<typeof IDL>["profile"];
+}) {
+  const provider = useAnchorProvider();
+  const toast = useToast();
+  const queryClient = useQueryClient();
+
+  const queryKey = ["legacy-todos", provider.publicKey.toBase58()];
+
+  const { data: todoResults, isLoading } = useQuery({
+    queryKey: [...queryKey, profile.todoCount],
+    queryFn: () => new TodoProgram(provider).fetchLegacyTodos(profile),
+  });
+
+  const { mutateAsync: sendTransaction } = useMutation({
+    mutationFn: async (
+      build: (program: TodoProgram) => ReturnType<TodoProgram["migrateTodo"]>
+    ) => {
+      const tx = await build(new TodoProgram(provider));
+      return provider.sendAndConfirm(tx);
+    },
+    onSuccess: () => {
+      toast({
+        title: "Transaction sent",
+        status: "success",
+      });
+
+      return queryClient.invalidateQueries({ queryKey });
+    },
+    onError: (error) => {
+      console.error(error);
+      toast({
+        title: "Transaction failed",
+        status: "error",
+      });
+    },
+  });
+
+  if (isLoading) {
+    return (
+      <Center as={Flex} direction="column" gap={4} py={8}>
+        <Spinner size="xl" colorScheme="blue" />
+        <Text>Loading...</Text>
+      </Center>
+    );
+  }
+
+  if (!todoResults?.length) {
+    return null;
+  }
+
+  const migrate = (todo: PublicKey) =>
+    sendTransaction((program) => program.migrateTodo(todo));
+
+  return (
+    <Flex direction="column" gap={4}>
+      <Text fontSize="xl" fontWeight="semibold">
+        Older todos
+      </Text>
+      <List>
+        {todoResults.map(({ todo, pubkey, actualIndex }) =>
+          todo ? (
+            <TodoItem
+              key={actualIndex}
+              content={todo.content}
+              completed={todo.completed}
+              onToggle={() =>
+                sendTransaction((program) => program.toggleLegacyTodo(actualIndex))
+              }
+              onDelete={() =>
+                sendTransaction((program) => program.deleteLegacyTodo(actualIndex))
+              }
+            />
+          ) : (
+            <ListItem
+              key={actualIndex}
+              borderBottomColor="gray.500"
+              borderBottomWidth="1px"
+              py={4}
+            >
+              <Flex justify="space-between" align="center">
+                <Text>Saved in an older format</Text>
+                <Button size="sm" colorScheme="blue" onClick={() => migrate(pubkey)}>
+                  Upgrade
+                </Button>
+              </Flex>
+            </ListItem>
+          )
+        )}
+      </List>
+    </Flex>
+  );
+}
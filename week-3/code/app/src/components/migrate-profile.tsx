@@ -0,0 +1,68 @@
+"use client";
+
+import useAnchorProvider from "@/hooks/use-anchor-provider";
+import TodoProgram, { MIGRATE_BATCH_SIZE } from "@/lib/todo-program";
+import { Button, Flex, Text, useToast } from "@chakra-ui/react";
+import { useMutation, useQueryClient } from "@tanstack/react-query";
+
+export default function MigrateProfile({
+  todoCount,
+  checked,
+}: {
+  todoCount: number;
+  checked: number;
+}) {
+  const provider = useAnchorProvider();
+  const toast = useToast();
+  const queryClient = useQueryClient();
+
+  const { isPending, mutateAsync } = useMutation({
+    mutationFn: async () => {
+      const program = new TodoProgram(provider);
+
+      // At least one transaction, which also bumps the version once every
+      // legacy todo index has been checked
+      let start = checked;
+      do {
+        const count = Math.min(MIGRATE_BATCH_SIZE, todoCount - start);
+        const tx = await program.migrateProfile(start, count);
+        await provider.sendAndConfirm(tx);
+        start += count;
+      } while (start < todoCount);
+    },
+    onSuccess: () => {
+      toast({
+        title: "Profile upgraded",
+        status: "success",
+      });
+
+      return queryClient.invalidateQueries({
+        queryKey: ["profile", provider.publicKey.toBase58()],
+      });
+    },
+    onError: (error) => {
+      console.error("Migration error:", error);
+      toast({
+        title: "Failed to upgrade profile",
+        status: "error",
+      });
+    },
+  });
+
+  return (
+    <Flex direction="column" gap={4} align="flex-start">
+      <Text>
+        Your profile was created by an older version of the app and has to be
+        upgraded before it can be used.
+      </Text>
+      <Button
+        isLoading={isPending}
+        onClick={() => mutateAsync()}
+        colorScheme="blue"
+        loadingText="Upgrading"
+      >
+        Upgrade profile
+      </Button>
+    </Flex>
+  );
+}
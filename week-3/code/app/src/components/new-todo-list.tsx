@@ -0,0 +1,111 @@
+"use client";
+
+import useAnchorProvider from "@/hooks/use-anchor-provider";
+import TodoProgram from "@/lib/todo-program";
+import {
+  Button,
+  Modal,
+  ModalOverlay,
+  ModalContent,
+  ModalHeader,
+  ModalFooter,
+  ModalBody,
+  ModalCloseButton,
+  useDisclosure,
+  FormControl,
+  FormLabel,
+  Input,
+  useToast,
+} from "@chakra-ui/react";
+import { IdlAccounts } from "@coral-xyz/anchor";
+import { useMutation, useQueryClient } from "@tanstack/react-query";
+import { useState } from "react";
+import { IDL } from "../../../target/types/todo_app";
+
+export default function NewTodoList({
+  profile,
+}: {
+  profile: IdlAccounts<typeof IDL>["profile"];
+}) {
+  const { isOpen, onOpen, onClose } = useDisclosure();
+  const toast = useToast();
+
+  const queryClient = useQueryClient();
+
+  const [name, setName] = useState("");
+
+  const provider = useAnchorProvider();
+
+  const { isPending, mutateAsync } = useMutation({
+    mutationKey: ["create-todo-list", provider.publicKey, profile.listCount],
+    mutationFn: async (name: string) => {
+      const program = new TodoProgram(provider);
+
+      const tx = await program.createTodoList(name, profile.listCount);
+
+      const signature = await provider.sendAndConfirm(tx);
+
+      return signature;
+    },
+    onSuccess: (tx) => {
+      console.log(tx);
+      toast({
+        title: "Transaction sent",
+        status: "success",
+      });
+
+      return queryClient.invalidateQueries({
+        queryKey: ["profile", provider.publicKey.toBase58()],
+      });
+    },
+    onError: (error) => {
+      console.error(error);
+    },
+    onSettled: () => {
+      onClose();
+    },
+  });
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    mutateAsync(name);
+  };
+
+  return (
+    <>
+      <Button onClick={onOpen} colorScheme="blue">
+        New list
+      </Button>
+      <Modal isOpen={isOpen} onClose={onClose}>
+        <ModalOverlay />
+        <ModalContent as="form" onSubmit={handleSubmit}>
+          <ModalHeader>New list</ModalHeader>
+          <ModalCloseButton />
+          <ModalBody pb={6}>
+            <FormControl>
+              <FormLabel>Name</FormLabel>
+              <Input
+                value={name}
+                onChange={(event) => setName(event.target.value)}
+                placeholder="Work, personal, ..."
+              />
+            </FormControl>
+          </ModalBody>
+
+          <ModalFooter>
+            <Button onClick={onClose}>Cancel</Button>
+            <Button
+              isLoading={isPending}
+              type="submit"
+              colorScheme="blue"
+              loadingText="Creating"
+              ml={3}
+            >
+              Create
+            </Button>
+          </ModalFooter>
+        </ModalContent>
+      </Modal>
+    </>
+  );
+}
@@ -23,9 +23,11 @@ import { useState } from "react";
 import { IDL } from "../../../target/types/todo_app";
 
 export default function NewTodo({
-  profile,
+  list,
+  listIndex,
 }: {
-  profile: IdlAccounts<typeof IDL>["profile"];
+  list: IdlAccounts<typeof IDL>["todoList"];
+  listIndex: number;
 }) {
   const { isOpen, onOpen, onClose } = useDisclosure();
 
@@ -38,12 +40,12 @@ export default function NewTodo({
   const provider = useAnchorProvider();
 
   const { isPending, mutateAsync } = useMutation({
-    mutationKey: ["create-todo", provider.publicKey, profile.todoCount],
+    mutationKey: ["create-todo", provider.publicKey, listIndex, list.todoCount],
     mutationFn: async (content: string) => {
       try {
         const program = new TodoProgram(provider);
 
-        const tx = await program.createTodo(content, profile.todoCount);
+        const tx = await program.createTodo(content, listIndex, list.todoCount);
         const signature = await provider.sendAndConfirm(tx);
 
         return signature;
@@ -60,7 +62,7 @@ export default function NewTodo({
       });
 
       return queryClient.invalidateQueries({
-        queryKey: ["profile", provider.publicKey.toBase58()],
+        queryKey: ["todo-lists", provider.publicKey.toBase58()],
       });
     },
     onError: (error) => {
@@ -9,18 +9,20 @@ import { IDL } from "../../../target/types/todo_app";
 import TodoItem from "./todo-item";
 
 export default function TodoList({
-  profile,
+  list,
+  listIndex,
 }: {
-  profile: IdlAccounts<typeof IDL>["profile"];
+  list: IdlAccounts<typeof IDL>["todoList"];
+  listIndex: number;
 }) {
   const provider = useAnchorProvider();
   const toast = useToast();
   const queryClient = useQueryClient();
 
   const { data: todoResults, isLoading } = useQuery({
-  queryKey: ["todos", profile.key.toBase58(), profile.todoCount],
-  enabled: !!profile,
-  queryFn: () => new TodoProgram(provider).fetchTodos(profile),
+  queryKey: ["todos", listIndex, list.todoCount],
+  enabled: !!list,
+  queryFn: () => new TodoProgram(provider).fetchTodos(listIndex, list),
 });
 
 console.log("todoResults", todoResults?.length);
@@ -28,7 +30,7 @@ console.log("todoResults", todoResults?.length);
   const { mutateAsync: toggleTodo } = useMutation({
     mutationFn: async (todoIndex: number) => {
       const program = new TodoProgram(provider);
-      const tx = await program.toggleTodo(todoIndex);
+      const tx = await program.toggleTodo(listIndex, todoIndex);
       const signature = await provider.sendAndConfirm(tx);
       return signature;
     },
@@ -40,7 +42,7 @@ console.log("todoResults", todoResults?.length);
       
       // Refresh todos
       queryClient.invalidateQueries({
-        queryKey: ["todos", profile.key.toBase58(), profile.todoCount],
+        queryKey: ["todos", listIndex, list.todoCount],
       });
     },
     onError: (error) => {
@@ -55,7 +57,7 @@ console.log("todoResults", todoResults?.length);
   const { mutateAsync: deleteTodo } = useMutation({
     mutationFn: async (todoIndex: number) => {
       const program = new TodoProgram(provider);
-      const tx = await program.deleteTodo(todoIndex);
+      const tx = await program.deleteTodo(listIndex, todoIndex);
       const signature = await provider.sendAndConfirm(tx);
       return signature;
     },
@@ -66,12 +68,12 @@ console.log("todoResults", todoResults?.length);
         status: "success",
       });
       
-      // Refresh both todos and profile (to update todo count)
+      // Refresh both todos and lists (to update todo count)
       queryClient.invalidateQueries({
-        queryKey: ["todos", profile.key.toBase58()],
+        queryKey: ["todos", listIndex],
       });
       queryClient.invalidateQueries({
-        queryKey: ["profile", provider.publicKey.toBase58()],
+        queryKey: ["todo-lists", provider.publicKey.toBase58()],
       });
     },
     onError: (error) => {
@@ -1,8 +1,11 @@
 "use client";
 
 import { WalletMultiButtonDynamic } from "@/components/connect-wallet-button";
+import LegacyTodos from "@/components/legacy-todos";
+import MigrateProfile from "@/components/migrate-profile";
 import NewProfile from "@/components/new-profile";
 import NewTodo from "@/components/new-todo";
+import NewTodoList from "@/components/new-todo-list";
 import useAnchorProvider from "@/hooks/use-anchor-provider";
 import TodoProgram from "@/lib/todo-program";
 import { Center, Flex, Spinner, Text } from "@chakra-ui/react";
@@ -14,13 +17,26 @@ export default function UserTodos() {
   const { publicKey } = useWallet();
   const provider = useAnchorProvider();
 
+  // Profiles from older versions can't be decoded until they're migrated
+  const { data: migration, isLoading: isLoadingMigration } = useQuery({
+    queryKey: ["profile", publicKey?.toBase58(), "migration"],
+    enabled: !!publicKey,
+    queryFn: () => new TodoProgram(provider).fetchMigrationState(),
+  });
+
   const { data: profile, isLoading } = useQuery({
     queryKey: ["profile", publicKey?.toBase58()],
-    enabled: !!publicKey,
+    enabled: !!publicKey && migration === null,
     queryFn: () => new TodoProgram(provider).fetchProfile(),
   });
 
-  if (isLoading) {
+  const { data: lists, isLoading: isLoadingLists } = useQuery({
+    queryKey: ["todo-lists", publicKey?.toBase58(), profile?.listCount],
+    enabled: !!profile,
+    queryFn: () => new TodoProgram(provider).fetchTodoLists(profile!),
+  });
+
+  if (isLoadingMigration || isLoading || isLoadingLists) {
     return (
       <Center as={Flex} direction="column" gap={4} py={8}>
         <Spinner size="xl" colorScheme="blue" />
@@ -29,6 +45,12 @@ export default function UserTodos() {
     );
   }
 
+  if (migration) {
+    return (
+      <MigrateProfile todoCount={migration.todoCount} checked={migration.checked} />
+    );
+  }
+
   if (!profile) {
     return <NewProfile />;
   }
@@ -41,8 +63,17 @@ export default function UserTodos() {
       <Text fontSize="2xl" fontWeight="bold">
         {profile?.name}
       </Text>
-      <TodoList profile={profile} />
-      <NewTodo profile={profile} />
+      <LegacyTodos profile={profile} />
+      {lists?.map(({ list, listIndex }) => (
+        <Flex key={listIndex} direction="column" gap={4}>
+          <Text fontSize="xl" fontWeight="semibold">
+            {list.name}
+          </Text>
+          <TodoList list={list} listIndex={listIndex} />
+          <NewTodo list={list} listIndex={listIndex} />
+        </Flex>
+      ))}
+      <NewTodoList profile={profile} />
     </Flex>
   );
 }
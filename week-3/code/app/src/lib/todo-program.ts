@@ -1,4 +1,11 @@
-import { AnchorProvider, IdlAccounts, Program, utils } from "@coral-xyz/anchor";
+import {
+  AnchorProvider,
+  BN,
+  IdlAccounts,
+  IdlTypes,
+  Program,
+  utils,
+} from "@coral-xyz/anchor";
 import { TodoApp } from "../../../target/types/todo_app";
 import { Cluster, PublicKey, SystemProgram } from "@solana/web3.js";
 
@@ -8,6 +15,27 @@ import idlJson from "../../../target/idl/todo_app.json";
 // Cast IDL JSON thành TodoApp type
 const IDL: TodoApp = idlJson as TodoApp;
 
+export type Priority = IdlTypes<TodoApp>["priority"];
+export type Role = IdlTypes<TodoApp>["role"];
+export type Recurrence = IdlTypes<TodoApp>["recurrence"];
+
+// `Profile::VERSION`; older profiles go through `migrateProfile` first
+export const PROFILE_VERSION = 10;
+
+// Legacy todo PDAs passed to each `migrateProfile` transaction
+export const MIGRATE_BATCH_SIZE = 20;
+
+// The first 256 todos keep the single byte seed they had while todoCount was a u8
+export function todoIndexSeed(index: number) {
+  if (index < 256) {
+    return Buffer.from([index]);
+  }
+
+  const seed = Buffer.alloc(4);
+  seed.writeUInt32LE(index);
+  return seed;
+}
+
 export default class TodoProgram {
   program: Program<TodoApp>;
   provider: AnchorProvider;
@@ -33,6 +61,204 @@ export default class TodoProgram {
     return builder.transaction();
   }
 
+  // Version 0 profiles pass the PDAs of their todo indices in
+  // batches: `count` indices from `start`, continuing until every index of
+  // `todoCount` has been passed
+  migrateProfile(start = 0, count = 0) {
+    const [profile] = PublicKey.findProgramAddressSync(
+      [utils.bytes.utf8.encode("profile"), this.provider.publicKey.toBytes()],
+      this.program.programId
+    );
+
+    const todos = Array.from({ length: count }, (_, i) => {
+      const [todo] = PublicKey.findProgramAddressSync(
+        [Buffer.from("todo"), profile.toBytes(), todoIndexSeed(start + i)],
+        this.program.programId
+      );
+
+      return { pubkey: todo, isSigner: false, isWritable: false };
+    });
+
+    const builder = this.program.methods
+      .migrateProfile()
+      .accounts({
+        user: this.provider.publicKey,
+        profile,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(todos);
+
+    return builder.transaction();
+  }
+
+  // `null` once the profile is up to date (or doesn't exist). Otherwise the
+  // legacy todo indices `migrateProfile` still has to check, from `checked`.
+  async fetchMigrationState() {
+    const [profile] = PublicKey.findProgramAddressSync(
+      [utils.bytes.utf8.encode("profile"), this.provider.publicKey.toBytes()],
+      this.program.programId
+    );
+
+    const info = await this.provider.connection.getAccountInfo(profile);
+    if (!info) {
+      return null;
+    }
+
+    try {
+      const account = this.program.coder.accounts.decode("profile", info.data);
+      if (account.version >= PROFILE_VERSION) {
+        return null;
+      }
+
+      return { todoCount: account.todoCount, checked: account.legacyTodosChecked };
+    } catch (error) {
+      // Not grown yet: the original layout ends with a `u8` todo count after
+      // the key, name and authority
+      const nameLength = info.data.readUInt32LE(8 + 32);
+      return { todoCount: info.data[8 + 32 + 4 + nameLength + 32], checked: 0 };
+    }
+  }
+
+  // Grows a todo allocated under an older layout so it can be loaded again
+  migrateTodo(todo: PublicKey) {
+    const [profile] = PublicKey.findProgramAddressSync(
+      [utils.bytes.utf8.encode("profile"), this.provider.publicKey.toBytes()],
+      this.program.programId
+    );
+
+    const builder = this.program.methods.migrateTodo().accounts({
+      user: this.provider.publicKey,
+      profile,
+      todo,
+      systemProgram: SystemProgram.programId,
+    });
+
+    return builder.transaction();
+  }
+
+  // Closes the given todos and lists, and the profile once none are left.
+  // Subtasks go before their todo, archive pages from the latest down.
+  closeProfile(accounts: PublicKey[]) {
+    const [profile] = PublicKey.findProgramAddressSync(
+      [utils.bytes.utf8.encode("profile"), this.provider.publicKey.toBytes()],
+      this.program.programId
+    );
+
+    const builder = this.program.methods
+      .closeProfile()
+      .accounts({
+        user: this.provider.publicKey,
+        profile,
+      })
+      .remainingAccounts(
+        accounts.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+      );
+
+    return builder.transaction();
+  }
+
+  collaboratorPda(member: PublicKey) {
+    const [profile] = PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), this.provider.publicKey.toBytes()],
+      this.program.programId
+    );
+
+    const [collaborator] = PublicKey.findProgramAddressSync(
+      [Buffer.from("collaborator"), profile.toBytes(), member.toBytes()],
+      this.program.programId
+    );
+
+    return [profile, collaborator];
+  }
+
+  addCollaborator(member: PublicKey, role: Role) {
+    const [profile, collaborator] = this.collaboratorPda(member);
+
+    const builder = this.program.methods.addCollaborator(member, role).accounts({
+      user: this.provider.publicKey,
+      profile,
+      collaborator,
+      systemProgram: SystemProgram.programId,
+    });
+
+    return builder.transaction();
+  }
+
+  removeCollaborator(member: PublicKey) {
+    const [profile, collaborator] = this.collaboratorPda(member);
+
+    const builder = this.program.methods.removeCollaborator().accounts({
+      user: this.provider.publicKey,
+      profile,
+      collaborator,
+    });
+
+    return builder.transaction();
+  }
+
+  proposeAuthority(newAuthority: PublicKey | null) {
+    const [profile] = PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), this.provider.publicKey.toBytes()],
+      this.program.programId
+    );
+
+    const builder = this.program.methods.proposeAuthority(newAuthority).accounts({
+      user: this.provider.publicKey,
+      profile,
+    });
+
+    return builder.transaction();
+  }
+
+  // The profile stays at the address derived from its creator
+  acceptAuthority(profile: PublicKey) {
+    const builder = this.program.methods.acceptAuthority().accounts({
+      newAuthority: this.provider.publicKey,
+      profile,
+    });
+
+    return builder.transaction();
+  }
+
+  handlePda(name: string) {
+    const [profile] = PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), this.provider.publicKey.toBytes()],
+      this.program.programId
+    );
+
+    const [handle] = PublicKey.findProgramAddressSync(
+      [Buffer.from("handle"), Buffer.from(name.toLowerCase())],
+      this.program.programId
+    );
+
+    return [profile, handle];
+  }
+
+  claimHandle(name: string) {
+    const [profile, handle] = this.handlePda(name);
+
+    const builder = this.program.methods.claimHandle(name).accounts({
+      user: this.provider.publicKey,
+      profile,
+      handle,
+      systemProgram: SystemProgram.programId,
+    });
+
+    return builder.transaction();
+  }
+
+  releaseHandle(name: string) {
+    const [profile, handle] = this.handlePda(name);
+
+    const builder = this.program.methods.releaseHandle().accounts({
+      user: this.provider.publicKey,
+      profile,
+      handle,
+    });
+
+    return builder.transaction();
+  }
+
   fetchProfile() {
     const [profile] = PublicKey.findProgramAddressSync(
       [utils.bytes.utf8.encode("profile"), this.provider.publicKey.toBytes()],
@@ -42,87 +268,437 @@ export default class TodoProgram {
     return this.program.account.profile.fetch(profile);
   }
 
-  createTodo(content: string, todoIndex: number) {
+  todoListPda(listIndex: number) {
+    const [profile] = PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), this.provider.publicKey.toBytes()],
+      this.program.programId
+    );
+
+    const index = Buffer.alloc(4);
+    index.writeUInt32LE(listIndex);
+
+    const [list] = PublicKey.findProgramAddressSync(
+      [Buffer.from("todo_list"), profile.toBytes(), index],
+      this.program.programId
+    );
+
+    return [profile, list];
+  }
+
+  // Todos created directly under the profile, before lists existed
+  legacyTodoPda(todoIndex: number) {
     const [profile] = PublicKey.findProgramAddressSync(
       [Buffer.from("profile"), this.provider.publicKey.toBytes()],
       this.program.programId
     );
 
     const [todo] = PublicKey.findProgramAddressSync(
-      [Buffer.from("todo"), profile.toBytes(), Buffer.from([todoIndex])],
+      [Buffer.from("todo"), profile.toBytes(), todoIndexSeed(todoIndex)],
       this.program.programId
     );
 
-    const builder = this.program.methods.createTodo(content).accounts({
+    return [profile, todo];
+  }
+
+  todoPda(listIndex: number, todoIndex: number) {
+    const [profile, list] = this.todoListPda(listIndex);
+
+    const [todo] = PublicKey.findProgramAddressSync(
+      [Buffer.from("todo"), list.toBytes(), todoIndexSeed(todoIndex)],
+      this.program.programId
+    );
+
+    return [profile, list, todo];
+  }
+
+  createTodoList(name: string, listIndex: number) {
+    const [profile, list] = this.todoListPda(listIndex);
+
+    const builder = this.program.methods.createTodoList(name).accounts({
       creator: this.provider.publicKey,
       profile,
-      todo,
+      list,
       systemProgram: SystemProgram.programId,
     });
 
     return builder.transaction();
   }
 
-  toggleTodo(todoIndex: number) {
-  const [profile] = PublicKey.findProgramAddressSync(
-    [Buffer.from("profile"), this.provider.publicKey.toBytes()],
-    this.program.programId
-  );
+  renameTodoList(name: string, listIndex: number) {
+    const [profile, list] = this.todoListPda(listIndex);
 
-  const [todo] = PublicKey.findProgramAddressSync(
-    [Buffer.from("todo"), profile.toBytes(), Buffer.from([todoIndex])],
-    this.program.programId
-  );
+    const builder = this.program.methods.renameTodoList(name).accounts({
+      user: this.provider.publicKey,
+      profile,
+      list,
+    });
+
+    return builder.transaction();
+  }
 
-  const builder = this.program.methods.toggleTodo().accounts({
-    user: this.provider.publicKey,
-    profile,
-    todo,
-  });
+  deleteTodoList(listIndex: number) {
+    const [profile, list] = this.todoListPda(listIndex);
 
-  return builder.transaction();
-}
+    const builder = this.program.methods.deleteTodoList().accounts({
+      user: this.provider.publicKey,
+      profile,
+      list,
+    });
 
-deleteTodo(todoIndex: number) {
-  const [profile] = PublicKey.findProgramAddressSync(
-    [Buffer.from("profile"), this.provider.publicKey.toBytes()],
-    this.program.programId
-  );
+    return builder.transaction();
+  }
 
-  const [todo] = PublicKey.findProgramAddressSync(
-    [Buffer.from("todo"), profile.toBytes(), Buffer.from([todoIndex])],
-    this.program.programId
-  );
+  async fetchTodoLists(profile: IdlAccounts<typeof IDL>["profile"]) {
+    const listResults: Array<{ list: any; listIndex: number }> = [];
 
-  const builder = this.program.methods.deleteTodo().accounts({
-    user: this.provider.publicKey,
-    profile,
-    todo,
-  });
+    for (let i = 0; i < profile.listCount; i++) {
+      const [, listPda] = this.todoListPda(i);
 
-  return builder.transaction();
-}
+      try {
+        const list = await this.program.account.todoList.fetch(listPda);
+        listResults.push({ list, listIndex: i });
+      } catch (error) {
+        // List deleted, skip
+        console.log(`List ${i} not found (deleted)`);
+      }
+    }
 
-async fetchTodos(profile: IdlAccounts<typeof IDL>["profile"]) {
-  const todoCount = profile.todoCount;
+    return listResults;
+  }
 
-  const todoResults: Array<{todo: any, actualIndex: number}> = [];
+  createTodo(
+    content: string,
+    listIndex: number,
+    todoIndex: number,
+    dueAt: BN | null = null,
+    priority: Priority = { medium: {} },
+    tags: string[] = []
+  ) {
+    const [profile, list, todo] = this.todoPda(listIndex, todoIndex);
 
-  for (let i = 0; i < todoCount; i++) {
-    const [todoPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("todo"), profile.key.toBytes(), Buffer.from([i])],
+    const builder = this.program.methods
+      .createTodo(content, dueAt, priority, tags)
+      .accounts({
+        creator: this.provider.publicKey,
+        profile,
+        list,
+        todo,
+        systemProgram: SystemProgram.programId,
+        tagIndex: tags.length > 0 ? this.tagIndexPda() : null,
+      });
+
+    return builder.transaction();
+  }
+
+  // `hash` is the SHA-256 of the body stored at `uri`
+  createLinkedTodo(
+    uri: string,
+    hash: number[],
+    listIndex: number,
+    todoIndex: number,
+    dueAt: BN | null = null,
+    priority: Priority = { medium: {} }
+  ) {
+    const [profile, list, todo] = this.todoPda(listIndex, todoIndex);
+
+    const builder = this.program.methods
+      .createLinkedTodo(uri, hash, dueAt, priority)
+      .accounts({
+        creator: this.provider.publicKey,
+        profile,
+        list,
+        todo,
+        systemProgram: SystemProgram.programId,
+        tagIndex: null,
+      });
+
+    return builder.transaction();
+  }
+
+  tagIndexPda() {
+    const [profile] = PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), this.provider.publicKey.toBytes()],
       this.program.programId
     );
 
-    try {
-      const todo = await this.program.account.todo.fetch(todoPda);
-      todoResults.push({ todo, actualIndex: i });
-    } catch (error) {
-      // Todo deleted, skip
-      console.log(`Todo ${i} not found (deleted)`);
+    const [tagIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("tag_index"), profile.toBytes()],
+      this.program.programId
+    );
+
+    return tagIndex;
+  }
+
+  createTagIndex() {
+    const [profile] = PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), this.provider.publicKey.toBytes()],
+      this.program.programId
+    );
+
+    const builder = this.program.methods.createTagIndex().accounts({
+      user: this.provider.publicKey,
+      profile,
+      tagIndex: this.tagIndexPda(),
+      systemProgram: SystemProgram.programId,
+    });
+
+    return builder.transaction();
+  }
+
+  // Todos tagged `tag`, from the profile's tag index
+  async fetchTodosByTag(tag: string) {
+    const tagIndex = await this.program.account.tagIndex.fetchNullable(
+      this.tagIndexPda()
+    );
+    const entry = tagIndex?.entries.find((entry) => entry.tag === tag);
+    if (!entry) return [];
+
+    const todos = await this.program.account.todo.fetchMultiple(entry.todos);
+    return todos.map((todo, i) => ({ pubkey: entry.todos[i], ...todo }));
+  }
+
+  // Creates one todo per entry, starting at the list's current todoCount
+  createTodos(contents: string[], listIndex: number, todoCount: number) {
+    const [profile, list] = this.todoListPda(listIndex);
+
+    const todos = contents.map((_, i) => {
+      const [, , todo] = this.todoPda(listIndex, todoCount + i);
+      return { pubkey: todo, isSigner: false, isWritable: true };
+    });
+
+    const builder = this.program.methods
+      .createTodos(contents)
+      .accounts({
+        creator: this.provider.publicKey,
+        profile,
+        list,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(todos);
+
+    return builder.transaction();
+  }
+
+  toggleTodos(listIndex: number, todoIndices: number[], allowOverdue = false) {
+    const [profile, list] = this.todoListPda(listIndex);
+
+    const todos = todoIndices.map((todoIndex) => {
+      const [, , todo] = this.todoPda(listIndex, todoIndex);
+      return { pubkey: todo, isSigner: false, isWritable: true };
+    });
+
+    const builder = this.program.methods
+      .toggleTodos(todoIndices, allowOverdue)
+      .accounts({
+        user: this.provider.publicKey,
+        profile,
+        list,
+      })
+      .remainingAccounts(todos);
+
+    return builder.transaction();
+  }
+
+  // Completing an overdue todo needs allowOverdue
+  // `prerequisites` are the todo's prerequisite indices, needed when
+  // completing. Completing a recurring todo also needs the list's todoCount,
+  // where the next occurrence is created.
+  toggleTodo(
+    listIndex: number,
+    todoIndex: number,
+    allowOverdue = false,
+    prerequisites: number[] = [],
+    nextTodoIndex: number | null = null
+  ) {
+    const [profile, list, todo] = this.todoPda(listIndex, todoIndex);
+
+    const remainingAccounts = this.todoMetas(listIndex, prerequisites);
+    if (nextTodoIndex !== null) {
+      const [, , nextTodo] = this.todoPda(listIndex, nextTodoIndex);
+      remainingAccounts.push({ pubkey: nextTodo, isSigner: false, isWritable: true });
     }
+
+    const builder = this.program.methods
+      .toggleTodo(allowOverdue)
+      .accounts({
+        user: this.provider.publicKey,
+        profile,
+        todo,
+        list: nextTodoIndex !== null ? list : null,
+        systemProgram: nextTodoIndex !== null ? SystemProgram.programId : null,
+      })
+      .remainingAccounts(remainingAccounts);
+
+    return builder.transaction();
+  }
+
+  // `reachable` lists every index reachable through `prerequisites`,
+  // including the prerequisites themselves
+  setPrerequisites(
+    listIndex: number,
+    todoIndex: number,
+    prerequisites: number[],
+    reachable: number[]
+  ) {
+    const [profile, list, todo] = this.todoPda(listIndex, todoIndex);
+
+    const builder = this.program.methods
+      .setPrerequisites(todoIndex, prerequisites)
+      .accounts({
+        user: this.provider.publicKey,
+        profile,
+        todo,
+        list,
+      })
+      .remainingAccounts(this.todoMetas(listIndex, reachable));
+
+    return builder.transaction();
+  }
+
+  todoMetas(listIndex: number, indices: number[]) {
+    return indices.map((index) => {
+      const [, , todo] = this.todoPda(listIndex, index);
+      return { pubkey: todo, isSigner: false, isWritable: false };
+    });
+  }
+
+  updateTodo(content: string, listIndex: number, todoIndex: number) {
+    const [profile, , todo] = this.todoPda(listIndex, todoIndex);
+
+    const builder = this.program.methods.updateTodo(content).accounts({
+      user: this.provider.publicKey,
+      profile,
+      authority: this.provider.publicKey,
+      todo,
+      systemProgram: SystemProgram.programId,
+    });
+
+    return builder.transaction();
+  }
+
+  updateTodoLink(
+    uri: string,
+    hash: number[],
+    listIndex: number,
+    todoIndex: number
+  ) {
+    const [profile, , todo] = this.todoPda(listIndex, todoIndex);
+
+    const builder = this.program.methods.updateTodoLink(uri, hash).accounts({
+      user: this.provider.publicKey,
+      profile,
+      authority: this.provider.publicKey,
+      todo,
+      systemProgram: SystemProgram.programId,
+    });
+
+    return builder.transaction();
+  }
+
+  updateTodoSchedule(
+    listIndex: number,
+    todoIndex: number,
+    dueAt: BN | null,
+    priority: Priority,
+    recurrence: Recurrence | null = null
+  ) {
+    const [profile, , todo] = this.todoPda(listIndex, todoIndex);
+
+    const builder = this.program.methods
+      .updateTodoSchedule(dueAt, priority, recurrence)
+      .accounts({
+        user: this.provider.publicKey,
+        profile,
+        todo,
+      });
+
+    return builder.transaction();
+  }
+
+  toggleLegacyTodo(todoIndex: number, allowOverdue = false) {
+    const [profile, todo] = this.legacyTodoPda(todoIndex);
+
+    const builder = this.program.methods.toggleTodo(allowOverdue).accounts({
+      user: this.provider.publicKey,
+      profile,
+      todo,
+      list: null,
+      systemProgram: null,
+    });
+
+    return builder.transaction();
+  }
+
+  deleteLegacyTodo(todoIndex: number) {
+    const [profile, todo] = this.legacyTodoPda(todoIndex);
+
+    const builder = this.program.methods.deleteTodo().accounts({
+      user: this.provider.publicKey,
+      profile,
+      authority: this.provider.publicKey,
+      todo,
+      list: null,
+      tagIndex: null,
+    });
+
+    return builder.transaction();
+  }
+
+  deleteTodo(listIndex: number, todoIndex: number, tagged = false) {
+    const [profile, list, todo] = this.todoPda(listIndex, todoIndex);
+
+    const builder = this.program.methods.deleteTodo().accounts({
+      user: this.provider.publicKey,
+      profile,
+      authority: this.provider.publicKey,
+      todo,
+      list,
+      tagIndex: tagged ? this.tagIndexPda() : null,
+    });
+
+    return builder.transaction();
   }
 
-  return todoResults;
+  async fetchTodos(listIndex: number, list: IdlAccounts<typeof IDL>["todoList"]) {
+    const todoResults: Array<{ todo: any; actualIndex: number }> = [];
+
+    for (let i = 0; i < list.todoCount; i++) {
+      const [, , todoPda] = this.todoPda(listIndex, i);
+
+      try {
+        const todo = await this.program.account.todo.fetch(todoPda);
+        todoResults.push({ todo, actualIndex: i });
+      } catch (error) {
+        // Todo deleted, skip
+        console.log(`Todo ${i} not found (deleted)`);
+      }
+    }
+
+    return todoResults;
+  }
+
+  // Todos still in an older layout come back with `todo: null` until they
+  // go through `migrateTodo`
+  async fetchLegacyTodos(profile: IdlAccounts<typeof IDL>["profile"]) {
+    const todoResults: Array<{ todo: any; pubkey: PublicKey; actualIndex: number }> = [];
+
+    for (let i = 0; i < profile.todoCount; i++) {
+      const [, todoPda] = this.legacyTodoPda(i);
+
+      const info = await this.provider.connection.getAccountInfo(todoPda);
+      if (!info) {
+        // Todo deleted, skip
+        continue;
+      }
+
+      try {
+        const todo = this.program.coder.accounts.decode("todo", info.data);
+        todoResults.push({ todo, pubkey: todoPda, actualIndex: i });
+      } catch (error) {
+        todoResults.push({ todo: null, pubkey: todoPda, actualIndex: i });
+      }
+    }
+
+    return todoResults;
+  }
 }
-}
\ No newline at end of file
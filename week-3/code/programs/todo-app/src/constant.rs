@@ -5,3 +5,40 @@ pub const PROFILE_SEED: &[u8] = b"profile";
 
 #[constant]
 pub const TODO_SEED: &[u8] = b"todo";
+
+#[constant]
+pub const TODO_LIST_SEED: &[u8] = b"todo_list";
+
+#[constant]
+pub const COLLABORATOR_SEED: &[u8] = b"collaborator";
+
+#[constant]
+pub const BOUNTY_SEED: &[u8] = b"bounty";
+
+#[constant]
+pub const HANDLE_SEED: &[u8] = b"handle";
+
+#[constant]
+pub const SUBTASK_SEED: &[u8] = b"subtask";
+
+#[constant]
+pub const TAG_INDEX_SEED: &[u8] = b"tag_index";
+
+#[constant]
+pub const COMMITMENT_SEED: &[u8] = b"commitment";
+
+#[constant]
+pub const SESSION_KEY_SEED: &[u8] = b"session_key";
+
+#[constant]
+pub const INTEGRATION_SEED: &[u8] = b"integration";
+
+// Seed of the PDA, under the calling program, that signs `create_todo_via_cpi`
+#[constant]
+pub const INTEGRATION_AUTHORITY_SEED: &[u8] = b"todo_integration";
+
+#[constant]
+pub const ARCHIVE_SEED: &[u8] = b"archive";
+
+#[constant]
+pub const COMMENT_SEED: &[u8] = b"comment";
\ No newline at end of file
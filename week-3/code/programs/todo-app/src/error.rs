@@ -10,4 +10,151 @@ pub enum AppError {
 
     #[msg("Invalid authority")]
     InvalidAuthority,
+
+    #[msg("Profile must be migrated to the latest layout")]
+    ProfileNotMigrated,
+
+    #[msg("Profile is already up to date")]
+    ProfileUpToDate,
+
+    #[msg("Invalid todo account")]
+    InvalidTodo,
+
+    #[msg("Every todo of the profile must be provided")]
+    MissingTodos,
+
+    #[msg("Invalid todo list")]
+    InvalidTodoList,
+
+    #[msg("Todo list still has todos")]
+    TodoListNotEmpty,
+
+    #[msg("Todo is overdue")]
+    TodoOverdue,
+
+    #[msg("Invalid collaborator")]
+    InvalidCollaborator,
+
+    #[msg("Bounty amount must be greater than zero")]
+    InvalidBountyAmount,
+
+    #[msg("Todo has an open bounty")]
+    BountyOpen,
+
+    #[msg("Bounty is already assigned")]
+    BountyAssigned,
+
+    #[msg("Invalid bounty assignee")]
+    InvalidAssignee,
+
+    #[msg("Todo is not completed")]
+    TodoNotCompleted,
+
+    #[msg("Token accounts are required for token bounties")]
+    MissingTokenAccounts,
+
+    #[msg("Number of todo accounts does not match the request")]
+    TodoAccountsMismatch,
+
+    #[msg("Handle must be 3-32 characters of a-z, 0-9 or _")]
+    InvalidHandle,
+
+    #[msg("Profile already has a handle")]
+    HandleAlreadyClaimed,
+
+    #[msg("Handle does not belong to this profile")]
+    InvalidHandleOwner,
+
+    #[msg("Todo still has open subtasks")]
+    SubtasksOpen,
+
+    #[msg("Todo still has subtasks")]
+    TodoHasSubtasks,
+
+    #[msg("Invalid subtask")]
+    InvalidSubtask,
+
+    #[msg("Todos take at most 4 distinct tags of 1-16 characters")]
+    InvalidTags,
+
+    #[msg("Tag index is required to change a todo's tags")]
+    MissingTagIndex,
+
+    #[msg("Invalid tag index")]
+    InvalidTagIndex,
+
+    #[msg("Todo book is full")]
+    TodoBookFull,
+
+    #[msg("Todo book slot is out of range or empty")]
+    InvalidTodoSlot,
+
+    #[msg("Invalid todo book")]
+    InvalidTodoBook,
+
+    #[msg("Todos take at most 4 distinct prerequisites other than themselves")]
+    InvalidPrerequisites,
+
+    #[msg("Todo has open prerequisites")]
+    PrerequisitesOpen,
+
+    #[msg("Prerequisites would form a cycle")]
+    DependencyCycle,
+
+    #[msg("Commitments need a rent-exempt stake and a deadline in the future")]
+    InvalidCommitment,
+
+    #[msg("Todo has an open commitment")]
+    CommitmentOpen,
+
+    #[msg("Todo was not completed before the commitment deadline")]
+    CommitmentMissed,
+
+    #[msg("Commitment deadline has not passed")]
+    CommitmentPending,
+
+    #[msg("Session expiry must be in the future")]
+    InvalidSessionExpiry,
+
+    #[msg("Invalid session key")]
+    InvalidSessionKey,
+
+    #[msg("Caller is not an approved integration")]
+    InvalidIntegration,
+
+    #[msg("Ciphertext must be 1-216 bytes")]
+    CiphertextTooLong,
+
+    #[msg("Profile has no encryption key")]
+    MissingEncryptionKey,
+
+    #[msg("Archive page is full")]
+    ArchiveFull,
+
+    #[msg("Todos are archived to the profile's latest archive page")]
+    InvalidArchive,
+
+    #[msg("Recurring todos need a due date and a non-zero interval")]
+    InvalidRecurrence,
+
+    #[msg("Completing a recurring todo needs its list, the next todo PDA and the system program")]
+    MissingRecurrenceAccounts,
+
+    #[msg("Comment body is too long")]
+    CommentTooLong,
+
+    #[msg("Invalid comment")]
+    InvalidComment,
+
+    #[msg("Todo still has comments")]
+    TodoHasComments,
+
+    #[msg("URI must be at most 200 bytes and use https://, ipfs:// or ar://")]
+    InvalidUri,
+
+    #[msg("Prerequisites must be todos already created in the same list")]
+    UnknownPrerequisite,
+
+    #[msg("Every todo reachable through the prerequisites must be provided")]
+    MissingPrerequisites,
 }
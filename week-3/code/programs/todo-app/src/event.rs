@@ -0,0 +1,118 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Priority;
+
+#[event]
+pub struct ProfileCreated {
+    pub profile: Pubkey,
+    pub authority: Pubkey,
+    pub name: String,
+    pub slot: u64,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub profile: Pubkey,
+    pub previous_authority: Pubkey,
+    pub authority: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct ProfileClosed {
+    pub profile: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct TodoCreated {
+    pub todo: Pubkey,
+    pub profile: Pubkey,
+    pub list: Pubkey,
+    pub content: String,
+    pub due_at: Option<i64>,
+    pub priority: Priority,
+    pub slot: u64,
+}
+
+#[event]
+pub struct TodoToggled {
+    pub todo: Pubkey,
+    pub profile: Pubkey,
+    pub completed: bool,
+    pub completed_at: Option<i64>,
+    pub slot: u64,
+}
+
+// Emitted by both `update_todo` and `update_todo_schedule`
+#[event]
+pub struct TodoUpdated {
+    pub todo: Pubkey,
+    pub profile: Pubkey,
+    pub content: String,
+    pub due_at: Option<i64>,
+    pub priority: Priority,
+    pub slot: u64,
+}
+
+#[event]
+pub struct TodoTagsUpdated {
+    pub todo: Pubkey,
+    pub profile: Pubkey,
+    pub tags: Vec<String>,
+    pub slot: u64,
+}
+
+#[event]
+pub struct PrerequisitesSet {
+    pub todo: Pubkey,
+    pub profile: Pubkey,
+    pub prerequisites: Vec<u32>,
+    pub slot: u64,
+}
+
+#[event]
+pub struct TodoDeleted {
+    pub todo: Pubkey,
+    pub profile: Pubkey,
+    pub slot: u64,
+}
+
+// Todo book entries have no account of their own; `entry` is their index in
+// the book
+#[event]
+pub struct BookTodoAdded {
+    pub book: Pubkey,
+    pub profile: Pubkey,
+    pub entry: u16,
+    pub content: String,
+    pub priority: Priority,
+    pub slot: u64,
+}
+
+#[event]
+pub struct BookTodoToggled {
+    pub book: Pubkey,
+    pub profile: Pubkey,
+    pub entry: u16,
+    pub completed: bool,
+    pub completed_at: Option<i64>,
+    pub slot: u64,
+}
+
+#[event]
+pub struct BookTodoRemoved {
+    pub book: Pubkey,
+    pub profile: Pubkey,
+    pub entry: u16,
+    pub slot: u64,
+}
+
+#[event]
+pub struct TodoArchived {
+    pub todo: Pubkey,
+    pub profile: Pubkey,
+    pub archive: Pubkey,
+    pub content_hash: [u8; 32],
+    pub slot: u64,
+}
@@ -1,13 +1,29 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::system_program::{
+    allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer,
+};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{
+    close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+    TransferChecked,
+};
+use bytemuck::Zeroable;
 use constant::*;
 use error::AppError;
-use state::{Profile, Todo};
+use event::*;
+use state::{
+    Archive, ArchiveRecord, Bounty, Collaborator, Comment, Commitment, ContentMode, EncryptedContent, EncryptionAlgorithm, Handle, Integration,
+    Priority, Profile, Recurrence, Role, SessionKey, SessionScope, Subtask, TagIndex, Todo, TodoBook,
+    TodoEntry, TodoList,
+};
 
 declare_id!("5qc4okxCxVSpm6B3Tp4QmHtuQV966Q4zZh4n87FL9mjS");
 
-mod constant;
-mod error;
-mod state;
+pub mod constant;
+pub mod error;
+mod event;
+pub mod state;
 
 #[program]
 pub mod todo_app {
@@ -24,111 +40,3246 @@ pub mod todo_app {
         profile.name = name;
         profile.authority = ctx.accounts.creator.key();
         profile.todo_count = 0;
+        profile.version = Profile::VERSION;
+        profile.active_todo_count = 0;
+        profile.list_count = 0;
+        profile.active_list_count = 0;
+        profile.collaborator_count = 0;
+        profile.handle = None;
+        profile.pending_authority = None;
+        profile.encryption_key = None;
+        profile.key_version = 0;
+        profile.archive_count = 0;
+        profile.open_count = 0;
+        profile.completed_count = 0;
+        profile.deleted_count = 0;
+        profile.last_completed_day = 0;
+        profile.current_streak = 0;
+        profile.legacy_todos_checked = 0;
+        profile.has_tag_index = false;
+        profile.todo_book_count = 0;
+        profile.session_key_count = 0;
+        profile.integration_count = 0;
+
+        emit!(ProfileCreated {
+            profile: key,
+            authority: profile.authority,
+            name: profile.name.clone(),
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_profile(ctx: Context<MigrateProfile>) -> Result<()> {
+        let profile_info = ctx.accounts.profile.to_account_info();
+
+        require_keys_eq!(
+            *profile_info.owner,
+            crate::ID,
+            ErrorCode::AccountOwnedByWrongProgram
+        );
+        require!(
+            profile_info.data.borrow().starts_with(Profile::DISCRIMINATOR),
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+
+        // Grow the account before decoding it: version 0 profiles, the only
+        // older layout, read their `u32` `todo_count`, `version` and every
+        // later field from the zeroed tail.
+        grow_account(
+            &profile_info,
+            &ctx.accounts.user,
+            &ctx.accounts.system_program,
+            8 + Profile::SPACE,
+        )?;
+
+        let mut profile = Profile::try_deserialize(&mut &profile_info.data.borrow()[..])?;
+
+        require_keys_eq!(
+            profile.authority,
+            ctx.accounts.user.key(),
+            AppError::InvalidAuthority
+        );
+        require!(
+            profile.version < Profile::VERSION,
+            AppError::ProfileUpToDate
+        );
+
+        // `active_todo_count` is rebuilt from the todos that still exist. The
+        // caller passes the PDAs of the next todo indices, in order from
+        // `legacy_todos_checked`, over as many calls as fit in a transaction;
+        // the profile stays unmigrated until every index is checked.
+        let start = profile.legacy_todos_checked;
+        require!(
+            start as usize + ctx.remaining_accounts.len() <= profile.todo_count as usize,
+            AppError::TodoAccountsMismatch
+        );
+
+        for (offset, todo_info) in ctx.remaining_accounts.iter().enumerate() {
+            require_keys_eq!(
+                todo_info.key(),
+                todo_address(profile_info.key, start + offset as u32),
+                AppError::InvalidTodo
+            );
+
+            if *todo_info.owner == crate::ID && !todo_info.data_is_empty() {
+                profile.active_todo_count += 1;
+            }
+        }
+
+        profile.legacy_todos_checked = start + ctx.remaining_accounts.len() as u32;
+        if profile.legacy_todos_checked < profile.todo_count {
+            return profile.try_serialize(&mut &mut profile_info.data.borrow_mut()[..]);
+        }
+
+        profile.version = Profile::VERSION;
+        profile.try_serialize(&mut &mut profile_info.data.borrow_mut()[..])?;
+
+        Ok(())
+    }
+
+    /// Grows a todo allocated before fields were appended to `Todo` (or shrunk
+    /// to its content by `update_todo` since) so `Account<Todo>` can decode it.
+    pub fn migrate_todo(ctx: Context<MigrateTodo>) -> Result<()> {
+        let todo_info = ctx.accounts.todo.to_account_info();
+
+        require_keys_eq!(
+            *todo_info.owner,
+            crate::ID,
+            ErrorCode::AccountOwnedByWrongProgram
+        );
+
+        // Decode as it will read once grown: missing fields from a zeroed tail
+        let mut data = todo_info.data.borrow().to_vec();
+        data.resize(data.len().max(8 + Todo::INIT_SPACE), 0);
+        let todo = Todo::try_deserialize(&mut &data[..])?;
+
+        require_keys_eq!(
+            todo.profile,
+            ctx.accounts.profile.key(),
+            AppError::InvalidTodo
+        );
+
+        let ciphertext_len = todo
+            .encrypted
+            .as_ref()
+            .map_or(0, |encrypted| encrypted.ciphertext.len());
+
+        grow_account(
+            &todo_info,
+            &ctx.accounts.user,
+            &ctx.accounts.system_program,
+            8 + Todo::space(todo.content.len(), ciphertext_len),
+        )
+    }
+
+    /// Passing `None` withdraws a pending proposal.
+    pub fn propose_authority(
+        ctx: Context<ProposeAuthority>,
+        new_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.profile.pending_authority = new_authority;
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+
+        let previous_authority = profile.authority;
+
+        profile.authority = ctx.accounts.new_authority.key();
+        profile.pending_authority = None;
+
+        emit!(AuthorityTransferred {
+            profile: profile.key(),
+            previous_authority,
+            authority: profile.authority,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    // Todos encrypted to earlier keys keep their `key_version` until re-encrypted
+    // with `update_encrypted_todo`
+    pub fn set_encryption_key(
+        ctx: Context<SetEncryptionKey>,
+        encryption_key: [u8; 32],
+    ) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+
+        profile.encryption_key = Some(encryption_key);
+        profile.key_version += 1;
+
+        Ok(())
+    }
+
+    pub fn add_collaborator(ctx: Context<AddCollaborator>, member: Pubkey, role: Role) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+
+        let collaborator = &mut ctx.accounts.collaborator;
+
+        collaborator.profile = profile.key();
+        collaborator.member = member;
+        collaborator.role = role;
+
+        profile.collaborator_count += 1;
+
+        Ok(())
+    }
+
+    pub fn remove_collaborator(ctx: Context<RemoveCollaborator>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        profile.collaborator_count -= 1;
+        Ok(())
+    }
+
+    pub fn create_session_key(
+        ctx: Context<CreateSessionKey>,
+        signer: Pubkey,
+        expires_at: i64,
+        scope: SessionScope,
+    ) -> Result<()> {
+        require_gt!(
+            expires_at,
+            Clock::get()?.unix_timestamp,
+            AppError::InvalidSessionExpiry
+        );
+
+        let session_key = &mut ctx.accounts.session_key;
+
+        session_key.profile = ctx.accounts.profile.key();
+        session_key.signer = signer;
+        session_key.expires_at = expires_at;
+        session_key.scope = scope;
+
+        ctx.accounts.profile.session_key_count += 1;
+
+        Ok(())
+    }
+
+    pub fn revoke_session_key(ctx: Context<RevokeSessionKey>) -> Result<()> {
+        ctx.accounts.profile.session_key_count -= 1;
+        Ok(())
+    }
+
+    pub fn approve_integration(ctx: Context<ApproveIntegration>, program: Pubkey) -> Result<()> {
+        let integration = &mut ctx.accounts.integration;
+
+        integration.profile = ctx.accounts.profile.key();
+        integration.program = program;
+
+        ctx.accounts.profile.integration_count += 1;
+
+        Ok(())
+    }
+
+    pub fn revoke_integration(ctx: Context<RevokeIntegration>) -> Result<()> {
+        ctx.accounts.profile.integration_count -= 1;
+        Ok(())
+    }
+
+    pub fn claim_handle(ctx: Context<ClaimHandle>, name: String) -> Result<()> {
+        let name = Handle::normalize(&name);
+        require!(Handle::is_valid(&name), AppError::InvalidHandle);
+
+        let profile = &mut ctx.accounts.profile;
+
+        let handle = &mut ctx.accounts.handle;
+
+        handle.profile = profile.key();
+        handle.name = name;
+
+        profile.handle = Some(handle.key());
+
+        Ok(())
+    }
+
+    pub fn release_handle(ctx: Context<ReleaseHandle>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        profile.handle = None;
+        Ok(())
+    }
+
+    pub fn transfer_handle(ctx: Context<TransferHandle>) -> Result<()> {
+        let handle = &mut ctx.accounts.handle;
+
+        handle.profile = ctx.accounts.new_profile.key();
+
+        ctx.accounts.profile.handle = None;
+        ctx.accounts.new_profile.handle = Some(handle.key());
+
+        Ok(())
+    }
+
+    pub fn create_todo_list(ctx: Context<CreateTodoList>, name: String) -> Result<()> {
+        require!(name.len() <= 100, AppError::NameTooLong);
+
+        let profile = &mut ctx.accounts.profile;
+
+        let list = &mut ctx.accounts.list;
+
+        list.profile = profile.key();
+        list.name = name;
+        list.todo_count = 0;
+        list.active_todo_count = 0;
+
+        profile.list_count += 1;
+        profile.active_list_count += 1;
+
+        Ok(())
+    }
+
+    pub fn rename_todo_list(ctx: Context<RenameTodoList>, name: String) -> Result<()> {
+        require!(name.len() <= 100, AppError::NameTooLong);
+
+        let list = &mut ctx.accounts.list;
+        list.name = name;
+
+        Ok(())
+    }
+
+    pub fn delete_todo_list(ctx: Context<DeleteTodoList>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        profile.active_list_count -= 1;
+        Ok(())
+    }
+
+    pub fn create_todo(
+        ctx: Context<CreateTodo>,
+        content: String,
+        due_at: Option<i64>,
+        priority: Priority,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        if content.len() > 200 {
+            return err!(AppError::ContentTooLong);
+        }
+
+        init_todo(
+            &mut ctx.accounts.profile,
+            &mut ctx.accounts.list,
+            &mut ctx.accounts.todo,
+            content,
+            due_at,
+            priority,
+        )?;
+
+        set_tags(
+            &mut ctx.accounts.todo,
+            ctx.accounts.tag_index.as_mut(),
+            tags,
+            &ctx.accounts.creator,
+            &ctx.accounts.system_program,
+        )
+    }
+
+    /// Entry point for other programs. Build with the `cpi` feature and call
+    /// `todo_app::cpi::create_todo_via_cpi`, signing `caller_authority` with
+    /// the calling program's `[INTEGRATION_AUTHORITY_SEED]` PDA.
+    pub fn create_todo_via_cpi(
+        ctx: Context<CreateTodoViaCpi>,
+        content: String,
+        due_at: Option<i64>,
+        priority: Priority,
+    ) -> Result<()> {
+        require!(
+            content.len() <= Todo::MAX_CONTENT_LEN,
+            AppError::ContentTooLong
+        );
+
+        init_todo(
+            &mut ctx.accounts.profile,
+            &mut ctx.accounts.list,
+            &mut ctx.accounts.todo,
+            content,
+            due_at,
+            priority,
+        )?;
+
+        ctx.accounts.todo.created_by = Some(ctx.accounts.integration.program);
+
+        Ok(())
+    }
+
+    pub fn create_encrypted_todo(
+        ctx: Context<CreateEncryptedTodo>,
+        ciphertext: Vec<u8>,
+        nonce: [u8; 24],
+        algorithm: EncryptionAlgorithm,
+        due_at: Option<i64>,
+        priority: Priority,
+    ) -> Result<()> {
+        let encrypted = encrypted_content(&ctx.accounts.profile, ciphertext, nonce, algorithm)?;
+
+        init_todo(
+            &mut ctx.accounts.profile,
+            &mut ctx.accounts.list,
+            &mut ctx.accounts.todo,
+            String::new(),
+            due_at,
+            priority,
+        )?;
+
+        ctx.accounts.todo.encrypted = Some(encrypted);
+
+        Ok(())
+    }
+
+    // Same accounts as `create_todo`; the todo is created without tags
+    pub fn create_linked_todo(
+        ctx: Context<CreateTodo>,
+        uri: String,
+        hash: [u8; 32],
+        due_at: Option<i64>,
+        priority: Priority,
+    ) -> Result<()> {
+        require!(Todo::uri_valid(&uri), AppError::InvalidUri);
+
+        init_todo(
+            &mut ctx.accounts.profile,
+            &mut ctx.accounts.list,
+            &mut ctx.accounts.todo,
+            uri,
+            due_at,
+            priority,
+        )?;
+
+        ctx.accounts.todo.content_mode = ContentMode::External { hash };
+
+        Ok(())
+    }
+
+    pub fn create_bounty_todo(
+        ctx: Context<CreateBountyTodo>,
+        content: String,
+        amount: u64,
+        reviewer: Pubkey,
+    ) -> Result<()> {
+        if content.len() > 200 {
+            return err!(AppError::ContentTooLong);
+        }
+        require_gt!(amount, 0, AppError::InvalidBountyAmount);
+
+        init_todo(
+            &mut ctx.accounts.profile,
+            &mut ctx.accounts.list,
+            &mut ctx.accounts.todo,
+            content,
+            None,
+            Priority::Medium,
+        )?;
+
+        let bounty = &mut ctx.accounts.bounty;
+
+        bounty.todo = ctx.accounts.todo.key();
+        bounty.creator = ctx.accounts.creator.key();
+        bounty.reviewer = reviewer;
+        bounty.assignee = None;
+        bounty.mint = ctx.accounts.mint.as_ref().map(|mint| mint.key());
+        bounty.amount = amount;
+        bounty.bump = ctx.bumps.bounty;
+
+        ctx.accounts.todo.bounty = Some(bounty.key());
+
+        // Lock the reward
+        match (
+            &ctx.accounts.mint,
+            &ctx.accounts.creator_token_account,
+            &ctx.accounts.vault,
+            &ctx.accounts.token_program,
+        ) {
+            (None, ..) => transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.creator.to_account_info(),
+                        to: ctx.accounts.bounty.to_account_info(),
+                    },
+                ),
+                amount,
+            ),
+            (Some(mint), Some(creator_token_account), Some(vault), Some(token_program)) => {
+                transfer_checked(
+                    CpiContext::new(
+                        token_program.to_account_info(),
+                        TransferChecked {
+                            from: creator_token_account.to_account_info(),
+                            mint: mint.to_account_info(),
+                            to: vault.to_account_info(),
+                            authority: ctx.accounts.creator.to_account_info(),
+                        },
+                    ),
+                    amount,
+                    mint.decimals,
+                )
+            }
+            _ => err!(AppError::MissingTokenAccounts),
+        }
+    }
+
+    pub fn assign_bounty(ctx: Context<AssignBounty>) -> Result<()> {
+        let bounty = &mut ctx.accounts.bounty;
+
+        require!(bounty.assignee.is_none(), AppError::BountyAssigned);
+
+        bounty.assignee = Some(ctx.accounts.assignee.key());
+
+        Ok(())
+    }
+
+    /// Lets the creator take the bounty back, e.g. to `cancel_bounty` after
+    /// the assignee or reviewer has gone quiet.
+    pub fn unassign_bounty(ctx: Context<UnassignBounty>) -> Result<()> {
+        let bounty = &mut ctx.accounts.bounty;
+
+        require!(bounty.assignee.is_some(), AppError::InvalidAssignee);
+
+        bounty.assignee = None;
+
+        Ok(())
+    }
+
+    // Lets the assignee mark the todo done without any rights on the profile
+    pub fn submit_bounty(ctx: Context<SubmitBounty>) -> Result<()> {
+        let clock = Clock::get()?;
+
+        let todo = &mut ctx.accounts.todo;
+        require!(!todo.has_open_subtasks(), AppError::SubtasksOpen);
+        check_prerequisites(todo, ctx.remaining_accounts)?;
+
+        if !todo.completed {
+            ctx.accounts.profile.record_completed(clock.unix_timestamp);
+        }
+
+        todo.completed = true;
+        todo.completed_at = Some(clock.unix_timestamp);
+
+        emit!(TodoToggled {
+            todo: todo.key(),
+            profile: todo.profile,
+            completed: todo.completed,
+            completed_at: todo.completed_at,
+            slot: clock.slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn approve_bounty(ctx: Context<ApproveBounty>) -> Result<()> {
+        require!(ctx.accounts.todo.completed, AppError::TodoNotCompleted);
+
+        ctx.accounts.todo.bounty = None;
+
+        let accounts = &ctx.accounts;
+        release_bounty(
+            &accounts.bounty,
+            accounts.assignee.to_account_info(),
+            accounts.creator.to_account_info(),
+            accounts.mint.as_deref(),
+            accounts.vault.as_deref(),
+            accounts.assignee_token_account.as_deref(),
+            accounts.token_program.as_ref(),
+        )
+    }
+
+    pub fn cancel_bounty(ctx: Context<CancelBounty>) -> Result<()> {
+        require!(
+            ctx.accounts.bounty.assignee.is_none(),
+            AppError::BountyAssigned
+        );
+
+        ctx.accounts.todo.bounty = None;
+
+        let accounts = &ctx.accounts;
+        release_bounty(
+            &accounts.bounty,
+            accounts.creator.to_account_info(),
+            accounts.creator.to_account_info(),
+            accounts.mint.as_deref(),
+            accounts.vault.as_deref(),
+            accounts.creator_token_account.as_deref(),
+            accounts.token_program.as_ref(),
+        )
+    }
+
+    pub fn create_commitment(
+        ctx: Context<CreateCommitment>,
+        amount: u64,
+        deadline: i64,
+        beneficiary: Pubkey,
+    ) -> Result<()> {
+        // Enough to leave even an empty beneficiary rent-exempt once forfeited
+        require_gte!(
+            amount,
+            Rent::get()?.minimum_balance(0),
+            AppError::InvalidCommitment
+        );
+        require_gt!(deadline, Clock::get()?.unix_timestamp, AppError::InvalidCommitment);
+
+        let commitment = &mut ctx.accounts.commitment;
+
+        commitment.todo = ctx.accounts.todo.key();
+        commitment.staker = ctx.accounts.user.key();
+        commitment.beneficiary = beneficiary;
+        commitment.amount = amount;
+        commitment.deadline = deadline;
+
+        ctx.accounts.todo.commitment = Some(commitment.key());
+
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: ctx.accounts.commitment.to_account_info(),
+                },
+            ),
+            amount,
+        )
+    }
+
+    // Stake and rent both go back to the staker through `close`
+    pub fn claim_commitment(ctx: Context<ClaimCommitment>) -> Result<()> {
+        require!(
+            ctx.accounts.commitment.is_met(&ctx.accounts.todo),
+            AppError::CommitmentMissed
+        );
+
+        ctx.accounts.todo.commitment = None;
+
+        Ok(())
+    }
+
+    // Callable by anyone once the deadline has passed without the todo done
+    pub fn forfeit_commitment(ctx: Context<ForfeitCommitment>) -> Result<()> {
+        let commitment = &ctx.accounts.commitment;
+
+        require_gt!(
+            Clock::get()?.unix_timestamp,
+            commitment.deadline,
+            AppError::CommitmentPending
+        );
+        require!(
+            !commitment.is_met(&ctx.accounts.todo),
+            AppError::CommitmentPending
+        );
+
+        commitment.sub_lamports(commitment.amount)?;
+        ctx.accounts.beneficiary.add_lamports(commitment.amount)?;
+
+        ctx.accounts.todo.commitment = None;
+
+        Ok(())
+    }
+
+    // `remaining_accounts` holds the todo PDAs for indices `list.todo_count..`,
+    // one per entry of `contents`, in order.
+    pub fn create_todos<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateTodos<'info>>,
+        contents: Vec<String>,
+    ) -> Result<()> {
+        require_eq!(
+            ctx.remaining_accounts.len(),
+            contents.len(),
+            AppError::TodoAccountsMismatch
+        );
+
+        let space = 8 + Todo::space(Todo::MAX_CONTENT_LEN, 0);
+        let lamports = Rent::get()?.minimum_balance(space);
+
+        for (content, todo_info) in contents.into_iter().zip(ctx.remaining_accounts) {
+            if content.len() > 200 {
+                return err!(AppError::ContentTooLong);
+            }
+
+            let list = &ctx.accounts.list;
+            let index_seed = Todo::index_seed(list.todo_count);
+            let (todo_key, bump) = Pubkey::find_program_address(
+                &[TODO_SEED, list.key().as_ref(), index_seed.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(todo_info.key(), todo_key, AppError::InvalidTodo);
+
+            create_pda_account(
+                &ctx.accounts.creator,
+                todo_info,
+                &ctx.accounts.system_program,
+                lamports,
+                space,
+                &[TODO_SEED, list.key().as_ref(), index_seed.as_ref(), &[bump]],
+            )?;
+
+            let mut todo = Account::<Todo>::try_from_unchecked(todo_info)?;
+            init_todo(
+                &mut ctx.accounts.profile,
+                &mut ctx.accounts.list,
+                &mut todo,
+                content,
+                None,
+                Priority::Medium,
+            )?;
+            todo.exit(&crate::ID)?;
+        }
+
+        Ok(())
+    }
+
+    // When completing, `remaining_accounts` holds the PDAs of the todo's
+    // prerequisites, in order, followed for recurring todos by the PDA the
+    // next occurrence goes to: the list's next todo.
+    pub fn toggle_todo<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ToggleTodo<'info>>,
+        allow_overdue: bool,
+    ) -> Result<()> {
+        let (prerequisites, rest) = ctx.remaining_accounts.split_at(
+            ctx.accounts
+                .todo
+                .prerequisites
+                .len()
+                .min(ctx.remaining_accounts.len()),
+        );
+        toggle(
+            &mut ctx.accounts.profile,
+            &mut ctx.accounts.todo,
+            allow_overdue,
+            prerequisites,
+        )?;
+
+        let todo = &ctx.accounts.todo;
+        let Some(recurrence) = todo.recurrence else {
+            return Ok(());
+        };
+        if !todo.completed || todo.next_occurrence.is_some() {
+            return Ok(());
+        }
+
+        let accounts = &mut *ctx.accounts;
+        let (Some(list), [next_todo], Some(system_program)) = (
+            accounts.list.as_mut(),
+            rest,
+            accounts.system_program.as_ref(),
+        ) else {
+            return err!(AppError::MissingRecurrenceAccounts);
+        };
+        require_keys_eq!(accounts.todo.list, list.key(), AppError::InvalidTodoList);
+
+        // The next occurrence is the list's next todo
+        let index_seed = Todo::index_seed(list.todo_count);
+        let (next_key, bump) = Pubkey::find_program_address(
+            &[TODO_SEED, list.key().as_ref(), index_seed.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(next_todo.key(), next_key, AppError::InvalidTodo);
+
+        let previous = &accounts.todo;
+        let space = 8 + Todo::space(
+            previous.content.len(),
+            previous
+                .encrypted
+                .as_ref()
+                .map_or(0, |encrypted| encrypted.ciphertext.len()),
+        );
+        create_pda_account(
+            &accounts.user,
+            next_todo,
+            system_program,
+            Rent::get()?.minimum_balance(space),
+            space,
+            &[TODO_SEED, list.key().as_ref(), index_seed.as_ref(), &[bump]],
+        )?;
+
+        // `update_todo_schedule` only allows recurrence with a due date
+        let now = Clock::get()?.unix_timestamp;
+        let due_at = previous
+            .due_at
+            .map(|due_at| recurrence.next_due(due_at, now).ok_or(AppError::InvalidRecurrence))
+            .transpose()?;
+
+        let mut next = Account::<Todo>::try_from_unchecked(next_todo)?;
+        init_todo(
+            &mut accounts.profile,
+            list,
+            &mut next,
+            previous.content.clone(),
+            due_at,
+            previous.priority,
+        )?;
+        next.encrypted = previous.encrypted.clone();
+        next.content_mode = previous.content_mode;
+        next.recurrence = Some(recurrence);
+        next.previous_occurrence = Some(previous.key());
+        next.exit(&crate::ID)?;
+
+        accounts.todo.next_occurrence = Some(next_key);
+
+        Ok(())
+    }
+
+    // `remaining_accounts` holds the todo PDAs of `list` for `indices`, in
+    // order. Todos with prerequisites or a recurrence can only be completed
+    // via `toggle_todo`.
+    pub fn toggle_todos<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ToggleTodos<'info>>,
+        indices: Vec<u32>,
+        allow_overdue: bool,
+    ) -> Result<()> {
+        require_eq!(
+            ctx.remaining_accounts.len(),
+            indices.len(),
+            AppError::TodoAccountsMismatch
+        );
+
+        let list_key = ctx.accounts.list.key();
+
+        for (index, todo_info) in indices.into_iter().zip(ctx.remaining_accounts) {
+            require_keys_eq!(
+                todo_info.key(),
+                todo_address(&list_key, index),
+                AppError::InvalidTodo
+            );
+            require!(todo_info.is_writable, ErrorCode::ConstraintMut);
+
+            let mut todo = Account::<Todo>::try_from(todo_info)?;
+            toggle(&mut ctx.accounts.profile, &mut todo, allow_overdue, &[])?;
+            require!(
+                !todo.completed || todo.recurrence.is_none() || todo.next_occurrence.is_some(),
+                AppError::MissingRecurrenceAccounts
+            );
+            todo.exit(&crate::ID)?;
+        }
 
         Ok(())
     }
 
-    pub fn create_todo(ctx: Context<CreateTodo>, content: String) -> Result<()> {
-        if content.len() > 200 {
-            return err!(AppError::ContentTooLong);
-        }
+    pub fn update_todo(ctx: Context<UpdateTodo>, content: String) -> Result<()> {
+        require!(
+            content.len() <= Todo::MAX_CONTENT_LEN,
+            AppError::ContentTooLong
+        );
+
+        let accounts = &mut *ctx.accounts;
+        resize_todo(
+            &accounts.todo,
+            &accounts.user,
+            &accounts.authority,
+            &accounts.system_program,
+            8 + Todo::space(content.len(), 0),
+        )?;
+
+        let todo = &mut accounts.todo;
+        todo.content = content;
+        todo.encrypted = None;
+        todo.content_mode = ContentMode::Inline;
+
+        emit_todo_updated(todo)
+    }
+
+    // Same accounts as `update_todo`, with the todo resized to fit `uri`
+    pub fn update_todo_link(ctx: Context<UpdateTodo>, uri: String, hash: [u8; 32]) -> Result<()> {
+        require!(Todo::uri_valid(&uri), AppError::InvalidUri);
+
+        let accounts = &mut *ctx.accounts;
+        resize_todo(
+            &accounts.todo,
+            &accounts.user,
+            &accounts.authority,
+            &accounts.system_program,
+            8 + Todo::space(uri.len(), 0),
+        )?;
+
+        let todo = &mut accounts.todo;
+        todo.content = uri;
+        todo.encrypted = None;
+        todo.content_mode = ContentMode::External { hash };
+
+        emit_todo_updated(todo)
+    }
+
+    // Also how todos move to a rotated key
+    pub fn update_encrypted_todo(
+        ctx: Context<UpdateEncryptedTodo>,
+        ciphertext: Vec<u8>,
+        nonce: [u8; 24],
+        algorithm: EncryptionAlgorithm,
+    ) -> Result<()> {
+        let space = 8 + Todo::space(0, ciphertext.len());
+        let encrypted = encrypted_content(&ctx.accounts.profile, ciphertext, nonce, algorithm)?;
+
+        let accounts = &mut *ctx.accounts;
+        resize_todo(
+            &accounts.todo,
+            &accounts.user,
+            &accounts.authority,
+            &accounts.system_program,
+            space,
+        )?;
+
+        let todo = &mut accounts.todo;
+        todo.content = String::new();
+        todo.encrypted = Some(encrypted);
+        todo.content_mode = ContentMode::Inline;
+
+        emit_todo_updated(todo)
+    }
+
+    pub fn update_todo_tags(ctx: Context<UpdateTodoTags>, tags: Vec<String>) -> Result<()> {
+        set_tags(
+            &mut ctx.accounts.todo,
+            ctx.accounts.tag_index.as_mut(),
+            tags,
+            &ctx.accounts.user,
+            &ctx.accounts.system_program,
+        )?;
+
+        let todo = &ctx.accounts.todo;
+        emit!(TodoTagsUpdated {
+            todo: todo.key(),
+            profile: todo.profile,
+            tags: todo.tags.clone(),
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    // `remaining_accounts` holds the PDAs of every todo reachable through
+    // `prerequisites`, in any order, so cycles back to `index` can be found
+    pub fn set_prerequisites(
+        ctx: Context<SetPrerequisites>,
+        index: u32,
+        prerequisites: Vec<u32>,
+    ) -> Result<()> {
+        let todo = &mut ctx.accounts.todo;
+        let parent = todo.seed_parent();
+
+        require_keys_eq!(todo.key(), todo_address(&parent, index), AppError::InvalidTodo);
+
+        // Uncreated todos would read as done, silently dropping the dependency
+        let todo_count = match &ctx.accounts.list {
+            Some(list) => list.todo_count,
+            None => {
+                require_keys_eq!(parent, todo.profile, AppError::InvalidTodoList);
+                ctx.accounts.profile.todo_count
+            }
+        };
+        require!(
+            prerequisites.iter().all(|prerequisite| *prerequisite < todo_count),
+            AppError::UnknownPrerequisite
+        );
+
+        require!(
+            prerequisites.len() <= Todo::MAX_PREREQUISITES
+                && prerequisites
+                    .iter()
+                    .enumerate()
+                    .all(|(i, prerequisite)| {
+                        *prerequisite != index && !prerequisites[..i].contains(prerequisite)
+                    }),
+            AppError::InvalidPrerequisites
+        );
+
+        // Depth-first walk of the dependency graph; deleted todos end a path
+        let mut visited = Vec::new();
+        let mut pending = prerequisites.clone();
+        while let Some(next) = pending.pop() {
+            require!(next != index, AppError::DependencyCycle);
+            if visited.contains(&next) {
+                continue;
+            }
+            visited.push(next);
+
+            let address = todo_address(&parent, next);
+            let todo_info = ctx
+                .remaining_accounts
+                .iter()
+                .find(|account| account.key() == address)
+                .ok_or(AppError::MissingPrerequisites)?;
+
+            if *todo_info.owner == crate::ID && !todo_info.data_is_empty() {
+                let prerequisite = Todo::try_deserialize(&mut &todo_info.data.borrow()[..])?;
+                pending.extend(prerequisite.prerequisites);
+            }
+        }
+
+        todo.prerequisites = prerequisites;
+
+        emit!(PrerequisitesSet {
+            todo: todo.key(),
+            profile: todo.profile,
+            prerequisites: todo.prerequisites.clone(),
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_todo_schedule(
+        ctx: Context<UpdateTodoSchedule>,
+        due_at: Option<i64>,
+        priority: Priority,
+        recurrence: Option<Recurrence>,
+    ) -> Result<()> {
+        // Legacy todos have no list for the next occurrence to go in
+        let todo = &mut ctx.accounts.todo;
+        if let Some(recurrence) = recurrence {
+            require!(
+                due_at.is_some() && recurrence.interval() > 0 && todo.list != Pubkey::default(),
+                AppError::InvalidRecurrence
+            );
+        }
+
+        todo.due_at = due_at;
+        todo.priority = priority;
+        todo.recurrence = recurrence;
+
+        emit_todo_updated(todo)
+    }
+
+    pub fn delete_todo(ctx: Context<DeleteTodo>) -> Result<()> {
+        let completed = ctx.accounts.todo.completed;
+        ctx.accounts.profile.record_deleted(completed);
+
+        remove_todo(
+            &mut ctx.accounts.profile,
+            &ctx.accounts.todo,
+            ctx.accounts.list.as_mut(),
+            ctx.accounts.tag_index.as_mut(),
+        )?;
+
+        emit!(TodoDeleted {
+            todo: ctx.accounts.todo.key(),
+            profile: ctx.accounts.profile.key(),
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_archive(ctx: Context<CreateArchive>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+
+        let archive = &mut ctx.accounts.archive;
+
+        archive.profile = profile.key();
+        archive.page = profile.archive_count;
+        archive.records = Vec::new();
+
+        profile.archive_count += 1;
+
+        Ok(())
+    }
+
+    // Records the todo in the latest archive page, then closes it like
+    // `delete_todo` does
+    pub fn archive_todo(ctx: Context<ArchiveTodo>, index: u32) -> Result<()> {
+        let todo = &ctx.accounts.todo;
+
+        require_keys_eq!(
+            todo.key(),
+            todo_address(&todo.seed_parent(), index),
+            AppError::InvalidTodo
+        );
+
+        let content_hash = match (&todo.encrypted, todo.content_mode) {
+            (Some(encrypted), _) => hash(&encrypted.ciphertext).to_bytes(),
+            (None, ContentMode::External { hash: body_hash }) => body_hash,
+            (None, ContentMode::Inline) => hash(todo.content.as_bytes()).to_bytes(),
+        };
+
+        let archive = &mut ctx.accounts.archive;
+        require!(
+            archive.records.len() < Archive::MAX_RECORDS,
+            AppError::ArchiveFull
+        );
+
+        archive.records.push(ArchiveRecord {
+            list: todo.list,
+            index,
+            content_hash,
+            completed_at: todo.completed_at.unwrap_or_default(),
+        });
+
+        // Grow a chunk at a time so most appends don't touch the account size
+        let capacity = archive.records.len().next_multiple_of(Archive::CHUNK_LEN);
+        grow_account(
+            &archive.to_account_info(),
+            &ctx.accounts.user,
+            &ctx.accounts.system_program,
+            8 + Archive::space(capacity),
+        )?;
+
+        remove_todo(
+            &mut ctx.accounts.profile,
+            &ctx.accounts.todo,
+            ctx.accounts.list.as_mut(),
+            ctx.accounts.tag_index.as_mut(),
+        )?;
+
+        emit!(TodoArchived {
+            todo: ctx.accounts.todo.key(),
+            profile: ctx.accounts.profile.key(),
+            archive: ctx.accounts.archive.key(),
+            content_hash,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_comment(ctx: Context<AddComment>, body: String) -> Result<()> {
+        require!(body.len() <= Comment::MAX_BODY_LEN, AppError::CommentTooLong);
+
+        let todo = &mut ctx.accounts.todo;
+
+        let comment = &mut ctx.accounts.comment;
+
+        comment.todo = todo.key();
+        comment.author = ctx.accounts.author.key();
+        comment.body = body;
+        comment.created_at = Clock::get()?.unix_timestamp;
+        comment.edited_at = None;
+
+        todo.comment_count += 1;
+        todo.active_comment_count += 1;
+
+        Ok(())
+    }
+
+    pub fn edit_comment(ctx: Context<EditComment>, body: String) -> Result<()> {
+        require!(body.len() <= Comment::MAX_BODY_LEN, AppError::CommentTooLong);
+
+        let comment = &mut ctx.accounts.comment;
+
+        comment.body = body;
+        comment.edited_at = Some(Clock::get()?.unix_timestamp);
+
+        Ok(())
+    }
+
+    pub fn delete_comment(ctx: Context<DeleteComment>) -> Result<()> {
+        ctx.accounts.todo.active_comment_count -= 1;
+        Ok(())
+    }
+
+    // Clears a todo's comments ahead of `delete_todo`. `remaining_accounts`
+    // holds `(comment, author)` pairs; each comment's rent goes back to its
+    // author.
+    pub fn delete_comments<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DeleteComments<'info>>,
+    ) -> Result<()> {
+        let pairs = ctx.remaining_accounts.chunks_exact(2);
+        require!(pairs.remainder().is_empty(), AppError::TodoAccountsMismatch);
+
+        let todo = &mut ctx.accounts.todo;
+
+        for pair in pairs {
+            let (comment_info, author) = (&pair[0], &pair[1]);
+            require!(comment_info.is_writable, ErrorCode::ConstraintMut);
+
+            let comment = Account::<Comment>::try_from(comment_info)?;
+            require_keys_eq!(comment.todo, todo.key(), AppError::InvalidComment);
+            require_keys_eq!(comment.author, author.key(), AppError::InvalidComment);
+
+            comment.close(author.clone())?;
+            todo.active_comment_count -= 1;
+        }
+
+        Ok(())
+    }
+
+    pub fn create_tag_index(ctx: Context<CreateTagIndex>) -> Result<()> {
+        ctx.accounts.tag_index.profile = ctx.accounts.profile.key();
+        ctx.accounts.tag_index.entries = Vec::new();
+        ctx.accounts.profile.has_tag_index = true;
+        Ok(())
+    }
+
+    pub fn create_todo_book(ctx: Context<CreateTodoBook>) -> Result<()> {
+        let mut book = ctx.accounts.book.load_init()?;
+        book.profile = ctx.accounts.profile.key();
+        ctx.accounts.profile.todo_book_count += 1;
+        Ok(())
+    }
+
+    pub fn add_book_todo(
+        ctx: Context<UpdateTodoBook>,
+        content: String,
+        priority: Priority,
+    ) -> Result<()> {
+        require!(
+            content.len() <= TodoBook::MAX_CONTENT_LEN,
+            AppError::ContentTooLong
+        );
+
+        let mut book = ctx.accounts.book.load_mut()?;
+        let slot = book.free_slot().ok_or(AppError::TodoBookFull)?;
+
+        let mut entry = TodoEntry {
+            created_at: Clock::get()?.unix_timestamp,
+            completed_at: 0,
+            content: [0; TodoBook::MAX_CONTENT_LEN],
+            content_len: content.len() as u8,
+            completed: 0,
+            priority: priority as u8,
+            _reserved: [0; 5],
+        };
+        entry.content[..content.len()].copy_from_slice(content.as_bytes());
+
+        book.entries[slot] = entry;
+        book.set_used(slot, true);
+        book.todo_count += 1;
+
+        ctx.accounts.profile.record_created();
+
+        emit!(BookTodoAdded {
+            book: ctx.accounts.book.key(),
+            profile: ctx.accounts.profile.key(),
+            entry: slot as u16,
+            content,
+            priority,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn toggle_book_todo(ctx: Context<UpdateTodoBook>, slot: u16) -> Result<()> {
+        let mut book = ctx.accounts.book.load_mut()?;
+        require!(book.is_used(slot as usize), AppError::InvalidTodoSlot);
+
+        let now = Clock::get()?.unix_timestamp;
+
+        let entry = &mut book.entries[slot as usize];
+        entry.completed ^= 1;
+        entry.completed_at = if entry.completed == 1 { now } else { 0 };
+
+        if entry.completed == 1 {
+            ctx.accounts.profile.record_completed(now);
+        } else {
+            ctx.accounts.profile.record_reopened();
+        }
+
+        emit!(BookTodoToggled {
+            book: ctx.accounts.book.key(),
+            profile: ctx.accounts.profile.key(),
+            entry: slot,
+            completed: entry.completed == 1,
+            completed_at: (entry.completed == 1).then_some(entry.completed_at),
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_book_todo(ctx: Context<UpdateTodoBook>, slot: u16) -> Result<()> {
+        let mut book = ctx.accounts.book.load_mut()?;
+        require!(book.is_used(slot as usize), AppError::InvalidTodoSlot);
+
+        ctx.accounts
+            .profile
+            .record_deleted(book.entries[slot as usize].completed == 1);
+
+        book.entries[slot as usize] = TodoEntry::zeroed();
+        book.set_used(slot as usize, false);
+        book.todo_count -= 1;
+
+        emit!(BookTodoRemoved {
+            book: ctx.accounts.book.key(),
+            profile: ctx.accounts.profile.key(),
+            entry: slot,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_todo_book(ctx: Context<CloseTodoBook>) -> Result<()> {
+        // Entries still in the book go with it
+        for entry in ctx.accounts.book.load()?.used_entries() {
+            ctx.accounts.profile.record_deleted(entry.completed == 1);
+        }
+        ctx.accounts.profile.todo_book_count -= 1;
+
+        Ok(())
+    }
+
+    pub fn create_subtask(ctx: Context<CreateSubtask>, content: String) -> Result<()> {
+        require!(
+            content.len() <= Subtask::MAX_CONTENT_LEN,
+            AppError::ContentTooLong
+        );
+
+        let todo = &mut ctx.accounts.todo;
+
+        let subtask = &mut ctx.accounts.subtask;
+
+        subtask.todo = todo.key();
+        subtask.profile = todo.profile;
+        subtask.content = content;
+        subtask.completed = false;
+
+        todo.subtask_count += 1;
+        todo.active_subtask_count += 1;
+
+        Ok(())
+    }
+
+    pub fn toggle_subtask(ctx: Context<ToggleSubtask>) -> Result<()> {
+        let todo = &mut ctx.accounts.todo;
+
+        let subtask = &mut ctx.accounts.subtask;
+        subtask.completed = !subtask.completed;
+
+        if subtask.completed {
+            todo.completed_subtask_count += 1;
+        } else {
+            todo.completed_subtask_count -= 1;
+        }
+
+        Ok(())
+    }
+
+    pub fn delete_subtask(ctx: Context<DeleteSubtask>) -> Result<()> {
+        let todo = &mut ctx.accounts.todo;
+
+        todo.active_subtask_count -= 1;
+        if ctx.accounts.subtask.completed {
+            todo.completed_subtask_count -= 1;
+        }
+
+        Ok(())
+    }
+
+    pub fn close_profile<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseProfile<'info>>,
+    ) -> Result<()> {
+        let user = ctx.accounts.user.to_account_info();
+        let profile = &mut ctx.accounts.profile;
+        let slot = Clock::get()?.slot;
+
+        // Todos, subtasks, todo books, lists, collaborators, session keys,
+        // integrations, archives, the handle and the tag index can be passed in
+        // any order, except that subtasks come before their todo and archive
+        // pages go from the latest down. Lists don't need to be empty since
+        // the whole profile is going away. Todos too small for the current
+        // layout go through `migrate_todo` first.
+        for account_info in ctx.remaining_accounts {
+            require!(account_info.is_writable, ErrorCode::ConstraintMut);
+
+            if account_info.data.borrow().starts_with(TodoList::DISCRIMINATOR) {
+                let list = Account::<TodoList>::try_from(account_info)?;
+                require_keys_eq!(list.profile, profile.key(), AppError::InvalidTodoList);
+
+                list.close(user.clone())?;
+                profile.active_list_count -= 1;
+            } else if account_info.data.borrow().starts_with(Collaborator::DISCRIMINATOR) {
+                let collaborator = Account::<Collaborator>::try_from(account_info)?;
+                require_keys_eq!(
+                    collaborator.profile,
+                    profile.key(),
+                    AppError::InvalidCollaborator
+                );
+
+                collaborator.close(user.clone())?;
+                profile.collaborator_count -= 1;
+            } else if account_info.data.borrow().starts_with(Handle::DISCRIMINATOR) {
+                let handle = Account::<Handle>::try_from(account_info)?;
+                require_keys_eq!(handle.profile, profile.key(), AppError::InvalidHandleOwner);
+
+                handle.close(user.clone())?;
+                profile.handle = None;
+            } else if account_info.data.borrow().starts_with(Subtask::DISCRIMINATOR) {
+                let subtask = Account::<Subtask>::try_from(account_info)?;
+                require_keys_eq!(subtask.profile, profile.key(), AppError::InvalidSubtask);
+
+                // Released from its todo, which must come later in the batch
+                let todo_info = ctx
+                    .remaining_accounts
+                    .iter()
+                    .find(|info| *info.key == subtask.todo)
+                    .ok_or(AppError::InvalidSubtask)?;
+                let mut todo = Account::<Todo>::try_from(todo_info)?;
+                todo.active_subtask_count -= 1;
+                if subtask.completed {
+                    todo.completed_subtask_count -= 1;
+                }
+                todo.exit(&crate::ID)?;
+
+                subtask.close(user.clone())?;
+            } else if account_info.data.borrow().starts_with(TagIndex::DISCRIMINATOR) {
+                let tag_index = Account::<TagIndex>::try_from(account_info)?;
+                require_keys_eq!(tag_index.profile, profile.key(), AppError::InvalidTagIndex);
+
+                tag_index.close(user.clone())?;
+                profile.has_tag_index = false;
+            } else if account_info.data.borrow().starts_with(TodoBook::DISCRIMINATOR) {
+                let book = AccountLoader::<TodoBook>::try_from(account_info)?;
+                require_keys_eq!(
+                    book.load()?.profile,
+                    profile.key(),
+                    AppError::InvalidTodoBook
+                );
+
+                for entry in book.load()?.used_entries() {
+                    profile.record_deleted(entry.completed == 1);
+                }
+                book.close(user.clone())?;
+                profile.todo_book_count -= 1;
+            } else if account_info.data.borrow().starts_with(SessionKey::DISCRIMINATOR) {
+                let session_key = Account::<SessionKey>::try_from(account_info)?;
+                require_keys_eq!(
+                    session_key.profile,
+                    profile.key(),
+                    AppError::InvalidSessionKey
+                );
+
+                session_key.close(user.clone())?;
+                profile.session_key_count -= 1;
+            } else if account_info.data.borrow().starts_with(Archive::DISCRIMINATOR) {
+                let archive = Account::<Archive>::try_from(account_info)?;
+                require_keys_eq!(archive.profile, profile.key(), AppError::InvalidArchive);
+                // Latest first, so `archive_count` keeps seeding the next page
+                require_eq!(archive.page + 1, profile.archive_count, AppError::InvalidArchive);
+
+                archive.close(user.clone())?;
+                profile.archive_count -= 1;
+            } else if account_info.data.borrow().starts_with(Integration::DISCRIMINATOR) {
+                let integration = Account::<Integration>::try_from(account_info)?;
+                require_keys_eq!(
+                    integration.profile,
+                    profile.key(),
+                    AppError::InvalidIntegration
+                );
+
+                integration.close(user.clone())?;
+                profile.integration_count -= 1;
+            } else {
+                let todo = Account::<Todo>::try_from(account_info)?;
+                require_keys_eq!(todo.profile, profile.key(), AppError::InvalidTodo);
+                require!(todo.bounty.is_none(), AppError::BountyOpen);
+                require!(todo.commitment.is_none(), AppError::CommitmentOpen);
+                require!(todo.active_comment_count == 0, AppError::TodoHasComments);
+                require!(todo.active_subtask_count == 0, AppError::TodoHasSubtasks);
+
+                todo.close(user.clone())?;
+                profile.active_todo_count -= 1;
+                profile.record_deleted(todo.completed);
+
+                emit!(TodoDeleted {
+                    todo: todo.key(),
+                    profile: profile.key(),
+                    slot,
+                });
+            }
+        }
+
+        // Large profiles can be torn down over several transactions; the
+        // profile itself goes with the last batch.
+        if profile.active_todo_count == 0
+            && profile.active_list_count == 0
+            && profile.collaborator_count == 0
+            && profile.handle.is_none()
+            && !profile.has_tag_index
+            && profile.todo_book_count == 0
+            && profile.session_key_count == 0
+            && profile.integration_count == 0
+            && profile.archive_count == 0
+        {
+            profile.close(user)?;
+
+            emit!(ProfileClosed {
+                profile: profile.key(),
+                slot,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// Shared by `toggle_todo` and `toggle_todos`. `prerequisites` is only read
+// when completing.
+fn toggle(
+    profile: &mut Profile,
+    todo: &mut Account<Todo>,
+    allow_overdue: bool,
+    prerequisites: &[AccountInfo],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    require!(
+        allow_overdue || !todo.is_overdue(now),
+        AppError::TodoOverdue
+    );
+    if !todo.completed {
+        require!(!todo.has_open_subtasks(), AppError::SubtasksOpen);
+        check_prerequisites(todo, prerequisites)?;
+    }
+
+    todo.completed = !todo.completed;
+    todo.completed_at = todo.completed.then_some(now);
+
+    if todo.completed {
+        profile.record_completed(now);
+    } else {
+        profile.record_reopened();
+    }
+
+    emit!(TodoToggled {
+        todo: todo.key(),
+        profile: todo.profile,
+        completed: todo.completed,
+        completed_at: todo.completed_at,
+        slot: clock.slot,
+    });
+
+    Ok(())
+}
+
+// `accounts` must be the PDAs of `todo.prerequisites`, in order. Deleted
+// prerequisites count as done.
+fn check_prerequisites(todo: &Todo, accounts: &[AccountInfo]) -> Result<()> {
+    require_eq!(
+        accounts.len(),
+        todo.prerequisites.len(),
+        AppError::TodoAccountsMismatch
+    );
+
+    let parent = todo.seed_parent();
+    for (index, todo_info) in todo.prerequisites.iter().zip(accounts) {
+        require_keys_eq!(
+            todo_info.key(),
+            todo_address(&parent, *index),
+            AppError::InvalidTodo
+        );
+
+        if *todo_info.owner == crate::ID && !todo_info.data_is_empty() {
+            let prerequisite = Todo::try_deserialize(&mut &todo_info.data.borrow()[..])?;
+            require!(prerequisite.completed, AppError::PrerequisitesOpen);
+        }
+    }
+
+    Ok(())
+}
+
+fn todo_address(parent: &Pubkey, index: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[TODO_SEED, parent.as_ref(), Todo::index_seed(index).as_ref()],
+        &crate::ID,
+    )
+    .0
+}
+
+// What `init` does for accounts that can't be declared in the `Accounts`
+// struct. Also works if someone already sent lamports to the address.
+fn create_pda_account<'info>(
+    payer: &Signer<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    lamports: u64,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let signer_seeds = &[seeds];
+
+    if account.lamports() == 0 {
+        return create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+                signer_seeds,
+            ),
+            lamports,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let shortfall = lamports.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+
+    allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Allocate {
+                account_to_allocate: account.clone(),
+            },
+            signer_seeds,
+        ),
+        space as u64,
+    )?;
+
+    assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Assign {
+                account_to_assign: account.clone(),
+            },
+            signer_seeds,
+        ),
+        &crate::ID,
+    )
+}
+
+// Shared by `create_todo`, `create_todos`, `create_todo_via_cpi` and
+// `create_bounty_todo`
+fn init_todo(
+    profile: &mut Account<Profile>,
+    list: &mut Account<TodoList>,
+    todo: &mut Account<Todo>,
+    content: String,
+    due_at: Option<i64>,
+    priority: Priority,
+) -> Result<()> {
+    todo.content = content;
+    todo.profile = profile.key();
+    todo.completed = false;
+    todo.list = list.key();
+    todo.due_at = due_at;
+    todo.priority = priority;
+    todo.created_at = Clock::get()?.unix_timestamp;
+    todo.completed_at = None;
+    todo.bounty = None;
+    todo.subtask_count = 0;
+    todo.active_subtask_count = 0;
+    todo.completed_subtask_count = 0;
+    todo.tags = Vec::new();
+    todo.prerequisites = Vec::new();
+    todo.commitment = None;
+    todo.created_by = None;
+    todo.encrypted = None;
+    todo.recurrence = None;
+    todo.previous_occurrence = None;
+    todo.next_occurrence = None;
+    todo.comment_count = 0;
+    todo.active_comment_count = 0;
+    todo.content_mode = ContentMode::Inline;
+
+    list.todo_count += 1;
+    list.active_todo_count += 1;
+
+    profile.active_todo_count += 1;
+    profile.record_created();
+
+    emit!(TodoCreated {
+        todo: todo.key(),
+        profile: todo.profile,
+        list: todo.list,
+        content: todo.content.clone(),
+        due_at: todo.due_at,
+        priority: todo.priority,
+        slot: Clock::get()?.slot,
+    });
+
+    Ok(())
+}
+
+// Shared by `delete_todo` and `archive_todo`. The `close` constraint takes
+// care of the account itself.
+fn remove_todo(
+    profile: &mut Account<Profile>,
+    todo: &Account<Todo>,
+    list: Option<&mut Account<TodoList>>,
+    tag_index: Option<&mut Account<TagIndex>>,
+) -> Result<()> {
+    if !todo.tags.is_empty() {
+        let tag_index = tag_index.ok_or(AppError::MissingTagIndex)?;
+
+        for tag in &todo.tags {
+            tag_index.remove(tag, todo.key());
+        }
+    }
+
+    // Todos created before lists existed have no list to update
+    match list {
+        Some(list) => {
+            require_keys_eq!(todo.list, list.key(), AppError::InvalidTodoList);
+            list.active_todo_count -= 1;
+        }
+        None => require_keys_eq!(todo.list, Pubkey::default(), AppError::InvalidTodoList),
+    }
+
+    profile.active_todo_count -= 1;
+
+    Ok(())
+}
+
+// Shared by `create_todo` and `update_todo_tags`. Moves `todo` between tag
+// index entries and grows the index when it needs more room.
+fn set_tags<'info>(
+    todo: &mut Account<'info, Todo>,
+    tag_index: Option<&mut Account<'info, TagIndex>>,
+    tags: Vec<String>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    require!(Todo::tags_valid(&tags), AppError::InvalidTags);
+
+    if todo.tags == tags {
+        return Ok(());
+    }
+
+    let tag_index = tag_index.ok_or(AppError::MissingTagIndex)?;
+
+    for tag in todo.tags.iter().filter(|tag| !tags.contains(tag)) {
+        tag_index.remove(tag, todo.key());
+    }
+    for tag in tags.iter().filter(|tag| !todo.tags.contains(tag)) {
+        tag_index.add(tag, todo.key());
+    }
+
+    grow_account(
+        &tag_index.to_account_info(),
+        payer,
+        system_program,
+        8 + tag_index.space(),
+    )?;
+
+    todo.tags = tags;
+
+    Ok(())
+}
+
+// Resizes a todo to `space` bytes for new content. `user` pays for growth,
+// but rent freed by shrinking goes to the profile owner, who paid for the
+// todo, even when a collaborator made the edit.
+fn resize_todo<'info>(
+    todo: &Account<'info, Todo>,
+    user: &Signer<'info>,
+    authority: &SystemAccount<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    let todo_info = todo.to_account_info();
+    if todo_info.data_len() <= space {
+        return grow_account(&todo_info, user, system_program, space);
+    }
+
+    todo_info.resize(space)?;
+
+    let refund = todo_info
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(space));
+    todo_info.sub_lamports(refund)?;
+    authority.add_lamports(refund)?;
+
+    Ok(())
+}
+
+// Grows `account` to `space` bytes if it is smaller, with `payer` topping up
+// the rent. Never shrinks.
+fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    if account.data_len() >= space {
+        return Ok(());
+    }
+
+    let lamports = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+
+    if lamports > 0 {
+        transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            lamports,
+        )?;
+    }
+
+    account.resize(space)?;
+
+    Ok(())
+}
+
+// Shared by `create_encrypted_todo` and `update_encrypted_todo`. The key
+// version always comes from the profile, not the client.
+fn encrypted_content(
+    profile: &Profile,
+    ciphertext: Vec<u8>,
+    nonce: [u8; 24],
+    algorithm: EncryptionAlgorithm,
+) -> Result<EncryptedContent> {
+    require!(
+        (1..=Todo::MAX_CIPHERTEXT_LEN).contains(&ciphertext.len()),
+        AppError::CiphertextTooLong
+    );
+    require!(
+        profile.encryption_key.is_some(),
+        AppError::MissingEncryptionKey
+    );
+
+    Ok(EncryptedContent {
+        algorithm,
+        nonce,
+        key_version: profile.key_version,
+        ciphertext,
+    })
+}
+
+fn emit_todo_updated(todo: &Account<Todo>) -> Result<()> {
+    emit!(TodoUpdated {
+        todo: todo.key(),
+        profile: todo.profile,
+        content: todo.content.clone(),
+        due_at: todo.due_at,
+        priority: todo.priority,
+        slot: Clock::get()?.slot,
+    });
+
+    Ok(())
+}
+
+// Pays out everything held in escrow to `recipient`. The vault's rent goes
+// back to `creator`; the bounty account itself is closed by its `close` constraint.
+fn release_bounty<'info>(
+    bounty: &Account<'info, Bounty>,
+    recipient: AccountInfo<'info>,
+    creator: AccountInfo<'info>,
+    mint: Option<&InterfaceAccount<'info, Mint>>,
+    vault: Option<&InterfaceAccount<'info, TokenAccount>>,
+    recipient_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    token_program: Option<&Interface<'info, TokenInterface>>,
+) -> Result<()> {
+    if bounty.mint.is_none() {
+        bounty.sub_lamports(bounty.amount)?;
+        recipient.add_lamports(bounty.amount)?;
+        return Ok(());
+    }
+
+    let (Some(mint), Some(vault), Some(recipient_token_account), Some(token_program)) =
+        (mint, vault, recipient_token_account, token_program)
+    else {
+        return err!(AppError::MissingTokenAccounts);
+    };
+
+    let signer_seeds: &[&[&[u8]]] = &[&[BOUNTY_SEED, bounty.todo.as_ref(), &[bounty.bump]]];
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: vault.to_account_info(),
+                mint: mint.to_account_info(),
+                to: recipient_token_account.to_account_info(),
+                authority: bounty.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        vault.amount,
+        mint.decimals,
+    )?;
+
+    close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: vault.to_account_info(),
+            destination: creator,
+            authority: bounty.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+#[derive(Accounts)]
+pub struct CreateProfile<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 /* account discriminator */ + Profile::SPACE,
+        seeds = [PROFILE_SEED, creator.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, Profile>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProfile<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: may still be in a layout `Account<Profile>` can't decode; owner,
+    /// discriminator and authority are checked in the handler. Not derived
+    /// from `user`, who may no longer be the creator.
+    #[account(mut)]
+    pub profile: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateTodo<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    /// CHECK: may be too small for `Account<Todo>` to decode; owner,
+    /// discriminator and profile are checked in the handler
+    #[account(mut)]
+    pub todo: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub new_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.pending_authority == Some(new_authority.key()) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+}
+
+#[derive(Accounts)]
+pub struct SetEncryptionKey<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+}
+
+#[derive(Accounts)]
+#[instruction(member: Pubkey)]
+pub struct AddCollaborator<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Collaborator::INIT_SPACE,
+        seeds = [COLLABORATOR_SEED, profile.key().as_ref(), member.as_ref()],
+        bump
+    )]
+    pub collaborator: Account<'info, Collaborator>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveCollaborator<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        close = user,
+        constraint = collaborator.profile == profile.key() @ AppError::InvalidCollaborator
+    )]
+    pub collaborator: Account<'info, Collaborator>,
+}
+
+#[derive(Accounts)]
+#[instruction(signer: Pubkey)]
+pub struct CreateSessionKey<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + SessionKey::INIT_SPACE,
+        seeds = [SESSION_KEY_SEED, profile.key().as_ref(), signer.as_ref()],
+        bump
+    )]
+    pub session_key: Account<'info, SessionKey>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSessionKey<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        close = user,
+        constraint = session_key.profile == profile.key() @ AppError::InvalidSessionKey
+    )]
+    pub session_key: Account<'info, SessionKey>,
+}
+
+#[derive(Accounts)]
+#[instruction(program: Pubkey)]
+pub struct ApproveIntegration<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Integration::INIT_SPACE,
+        seeds = [INTEGRATION_SEED, profile.key().as_ref(), program.as_ref()],
+        bump
+    )]
+    pub integration: Account<'info, Integration>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeIntegration<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        close = user,
+        constraint = integration.profile == profile.key() @ AppError::InvalidIntegration
+    )]
+    pub integration: Account<'info, Integration>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ClaimHandle<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated,
+        constraint = profile.handle.is_none() @ AppError::HandleAlreadyClaimed
+    )]
+    pub profile: Account<'info, Profile>,
+
+    // `init` fails if someone already holds the handle
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Handle::INIT_SPACE,
+        seeds = [HANDLE_SEED, Handle::seed(&name).as_slice()],
+        bump
+    )]
+    pub handle: Account<'info, Handle>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseHandle<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        close = user,
+        constraint = handle.profile == profile.key() @ AppError::InvalidHandleOwner
+    )]
+    pub handle: Account<'info, Handle>,
+}
+
+// Both authorities sign so nobody is handed a handle they didn't ask for
+#[derive(Accounts)]
+pub struct TransferHandle<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    pub new_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = new_profile.authority == new_authority.key() @ AppError::InvalidAuthority,
+        constraint = new_profile.version == Profile::VERSION @ AppError::ProfileNotMigrated,
+        constraint = new_profile.handle.is_none() @ AppError::HandleAlreadyClaimed
+    )]
+    pub new_profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = handle.profile == profile.key() @ AppError::InvalidHandleOwner
+    )]
+    pub handle: Account<'info, Handle>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTodoList<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == creator.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + TodoList::INIT_SPACE,
+        seeds = [TODO_LIST_SEED, profile.key().as_ref(), profile.list_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub list: Account<'info, TodoList>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenameTodoList<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = list.profile == profile.key() @ AppError::InvalidTodoList
+    )]
+    pub list: Account<'info, TodoList>,
+}
+
+#[derive(Accounts)]
+pub struct DeleteTodoList<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        close = user,
+        constraint = list.profile == profile.key() @ AppError::InvalidTodoList,
+        constraint = list.active_todo_count == 0 @ AppError::TodoListNotEmpty
+    )]
+    pub list: Account<'info, TodoList>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTodo<'info> {
+    #[account(mut)]
+    creator: Signer<'info>,
+
+    #[account(mut, 
+        // has_one = authority
+        constraint = profile.authorizes(&creator.key(), collaborator.as_deref(), Role::Editor)
+            || session_key.as_ref().is_some_and(|session_key| {
+                session_key.scope.create_todo
+                    && session_key.is_valid(&profile.key(), &creator.key())
+            }) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = list.profile == profile.key() @ AppError::InvalidTodoList
+    )]
+    list: Account<'info, TodoList>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Todo::space(Todo::MAX_CONTENT_LEN, 0),
+        seeds = [TODO_SEED, list.key().as_ref(), Todo::index_seed(list.todo_count).as_ref()],
+        bump
+    )]
+    todo: Account<'info, Todo>,
+
+    system_program: Program<'info, System>,
+
+    // Required when `tags` is not empty
+    #[account(
+        mut,
+        seeds = [TAG_INDEX_SEED, profile.key().as_ref()],
+        bump
+    )]
+    tag_index: Option<Account<'info, TagIndex>>,
+
+    // Required when `creator` is not the profile authority
+    collaborator: Option<Account<'info, Collaborator>>,
+
+    // Required when `creator` signs with a session key; it also pays the rent
+    session_key: Option<Account<'info, SessionKey>>,
+}
+
+#[derive(Accounts)]
+#[instruction(ciphertext: Vec<u8>)]
+pub struct CreateEncryptedTodo<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authorizes(&creator.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = list.profile == profile.key() @ AppError::InvalidTodoList
+    )]
+    pub list: Account<'info, TodoList>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Todo::space(0, ciphertext.len()),
+        seeds = [TODO_SEED, list.key().as_ref(), Todo::index_seed(list.todo_count).as_ref()],
+        bump
+    )]
+    pub todo: Account<'info, Todo>,
+
+    pub system_program: Program<'info, System>,
+
+    // Required when `creator` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTodoViaCpi<'info> {
+    #[account(
+        constraint = caller_authority.key() == Integration::caller_authority(&integration.program) @ AppError::InvalidIntegration
+    )]
+    pub caller_authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        seeds = [INTEGRATION_SEED, profile.key().as_ref(), integration.program.as_ref()],
+        bump
+    )]
+    pub integration: Account<'info, Integration>,
+
+    #[account(
+        mut,
+        constraint = list.profile == profile.key() @ AppError::InvalidTodoList
+    )]
+    pub list: Account<'info, TodoList>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Todo::space(Todo::MAX_CONTENT_LEN, 0),
+        seeds = [TODO_SEED, list.key().as_ref(), Todo::index_seed(list.todo_count).as_ref()],
+        bump
+    )]
+    pub todo: Account<'info, Todo>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTodos<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authorizes(&creator.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = list.profile == profile.key() @ AppError::InvalidTodoList
+    )]
+    pub list: Account<'info, TodoList>,
+
+    pub system_program: Program<'info, System>,
+
+    // Required when `creator` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct ToggleTodos<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        constraint = list.profile == profile.key() @ AppError::InvalidTodoList
+    )]
+    pub list: Account<'info, TodoList>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateBountyTodo<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authorizes(&creator.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = list.profile == profile.key() @ AppError::InvalidTodoList
+    )]
+    pub list: Account<'info, TodoList>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Todo::space(Todo::MAX_CONTENT_LEN, 0),
+        seeds = [TODO_SEED, list.key().as_ref(), Todo::index_seed(list.todo_count).as_ref()],
+        bump
+    )]
+    pub todo: Account<'info, Todo>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Bounty::INIT_SPACE,
+        seeds = [BOUNTY_SEED, todo.key().as_ref()],
+        bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    pub system_program: Program<'info, System>,
+
+    // Required when `creator` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+
+    // The remaining accounts are only needed for token bounties
+    pub mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = creator,
+        token::token_program = token_program,
+    )]
+    pub creator_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = mint,
+        associated_token::authority = bounty,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+}
+
+#[derive(Accounts)]
+pub struct AssignBounty<'info> {
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = creator @ AppError::InvalidAuthority
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    // A system account, so `approve_bounty` can pay it out
+    pub assignee: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnassignBounty<'info> {
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = creator @ AppError::InvalidAuthority
+    )]
+    pub bounty: Account<'info, Bounty>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitBounty<'info> {
+    pub assignee: Signer<'info>,
+
+    #[account(
+        has_one = todo,
+        constraint = bounty.assignee == Some(assignee.key()) @ AppError::InvalidAssignee
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    #[account(mut)]
+    pub todo: Account<'info, Todo>,
+
+    #[account(
+        mut,
+        address = todo.profile @ AppError::InvalidTodo,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveBounty<'info> {
+    pub reviewer: Signer<'info>,
+
+    #[account(
+        mut,
+        close = creator,
+        has_one = reviewer @ AppError::InvalidAuthority,
+        has_one = creator,
+        has_one = todo,
+        constraint = bounty.assignee == Some(assignee.key()) @ AppError::InvalidAssignee
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    #[account(mut)]
+    pub todo: Account<'info, Todo>,
+
+    #[account(mut)]
+    pub creator: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub assignee: SystemAccount<'info>,
+
+    // The remaining accounts are only needed for token bounties
+    #[account(
+        constraint = bounty.mint == Some(mint.key()) @ AppError::MissingTokenAccounts
+    )]
+    pub mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = bounty,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = assignee,
+        token::token_program = token_program,
+    )]
+    pub assignee_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBounty<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        close = creator,
+        has_one = creator @ AppError::InvalidAuthority,
+        has_one = todo
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    #[account(mut)]
+    pub todo: Account<'info, Todo>,
+
+    // The remaining accounts are only needed for token bounties
+    #[account(
+        constraint = bounty.mint == Some(mint.key()) @ AppError::MissingTokenAccounts
+    )]
+    pub mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = bounty,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = creator,
+        token::token_program = token_program,
+    )]
+    pub creator_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCommitment<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = todo.profile == profile.key() @ AppError::InvalidAuthority,
+        constraint = !todo.completed @ AppError::InvalidTodo
+    )]
+    pub todo: Account<'info, Todo>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Commitment::INIT_SPACE,
+        seeds = [COMMITMENT_SEED, todo.key().as_ref()],
+        bump
+    )]
+    pub commitment: Account<'info, Commitment>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCommitment<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(
+        mut,
+        close = staker,
+        has_one = staker @ AppError::InvalidAuthority,
+        has_one = todo
+    )]
+    pub commitment: Account<'info, Commitment>,
+
+    #[account(mut)]
+    pub todo: Account<'info, Todo>,
+}
+
+#[derive(Accounts)]
+pub struct ForfeitCommitment<'info> {
+    pub caller: Signer<'info>,
+
+    // Rent goes back to the staker, only the stake is forfeited
+    #[account(
+        mut,
+        close = staker,
+        has_one = staker,
+        has_one = beneficiary,
+        has_one = todo
+    )]
+    pub commitment: Account<'info, Commitment>,
+
+    #[account(mut)]
+    pub todo: Account<'info, Todo>,
+
+    #[account(mut)]
+    pub staker: SystemAccount<'info>,
+
+    /// CHECK: matched by `has_one`; only credited, so it may be owned by any
+    /// program, e.g. a DAO treasury PDA
+    #[account(mut)]
+    pub beneficiary: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ToggleTodo<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor)
+            || session_key.as_ref().is_some_and(|session_key| {
+                session_key.scope.toggle_todo
+                    && session_key.is_valid(&profile.key(), &user.key())
+            }) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+    
+    #[account(
+        mut,
+        constraint = todo.profile == profile.key() @ AppError::InvalidAuthority
+    )]
+    pub todo: Account<'info, Todo>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+
+    // Required when `user` signs with a session key
+    pub session_key: Option<Account<'info, SessionKey>>,
+
+    // Required when completing a recurring todo
+    #[account(mut)]
+    pub list: Option<Account<'info, TodoList>>,
+
+    // Required when completing a recurring todo
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTodo<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    // Receives the rent freed when the todo shrinks
+    #[account(mut, address = profile.authority @ AppError::InvalidAuthority)]
+    pub authority: SystemAccount<'info>,
+
+    // Resized to fit the new content in the handler
+    #[account(
+        mut,
+        constraint = todo.profile == profile.key() @ AppError::InvalidAuthority
+    )]
+    pub todo: Account<'info, Todo>,
+
+    pub system_program: Program<'info, System>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEncryptedTodo<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    // Receives the rent freed when the todo shrinks
+    #[account(mut, address = profile.authority @ AppError::InvalidAuthority)]
+    pub authority: SystemAccount<'info>,
+
+    // Resized to fit the new ciphertext in the handler
+    #[account(
+        mut,
+        constraint = todo.profile == profile.key() @ AppError::InvalidAuthority
+    )]
+    pub todo: Account<'info, Todo>,
+
+    pub system_program: Program<'info, System>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTodoTags<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = todo.profile == profile.key() @ AppError::InvalidAuthority
+    )]
+    pub todo: Account<'info, Todo>,
+
+    // Required when the todo's tags change
+    #[account(
+        mut,
+        seeds = [TAG_INDEX_SEED, profile.key().as_ref()],
+        bump
+    )]
+    pub tag_index: Option<Account<'info, TagIndex>>,
+
+    pub system_program: Program<'info, System>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrerequisites<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = todo.profile == profile.key() @ AppError::InvalidAuthority
+    )]
+    pub todo: Account<'info, Todo>,
+
+    // The list the todo belongs to, if it was created under one
+    #[account(constraint = list.key() == todo.list @ AppError::InvalidTodoList)]
+    pub list: Option<Account<'info, TodoList>>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTodoSchedule<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = todo.profile == profile.key() @ AppError::InvalidAuthority
+    )]
+    pub todo: Account<'info, Todo>,
 
-        let profile = &mut ctx.accounts.profile;
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
 
-        let todo = &mut ctx.accounts.todo;
+#[derive(Accounts)]
+pub struct DeleteTodo<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Admin) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
 
-        todo.content = content;
-        todo.profile = profile.key();
-        todo.completed = false;
+    // Rent always goes back to the profile owner, even when a collaborator deletes
+    #[account(mut, address = profile.authority @ AppError::InvalidAuthority)]
+    pub authority: SystemAccount<'info>,
+    
+    #[account(
+        mut,
+        close = authority, // This closes the account and returns rent to the profile owner
+        constraint = todo.profile == profile.key() @ AppError::InvalidAuthority,
+        constraint = todo.bounty.is_none() @ AppError::BountyOpen,
+        constraint = todo.commitment.is_none() @ AppError::CommitmentOpen,
+        constraint = todo.active_subtask_count == 0 @ AppError::TodoHasSubtasks,
+        constraint = todo.active_comment_count == 0 @ AppError::TodoHasComments
+    )]
+    pub todo: Account<'info, Todo>,
 
-        profile.todo_count += 1;
+    // The list the todo belongs to, if it was created under one
+    #[account(mut)]
+    pub list: Option<Account<'info, TodoList>>,
 
-        Ok(())
-    }
+    // Required when the todo has tags
+    #[account(
+        mut,
+        seeds = [TAG_INDEX_SEED, profile.key().as_ref()],
+        bump
+    )]
+    pub tag_index: Option<Account<'info, TagIndex>>,
 
-    pub fn toggle_todo(ctx: Context<ToggleTodo>) -> Result<()> {
-        let todo = &mut ctx.accounts.todo;
-        todo.completed = !todo.completed;
-        Ok(())
-    }
-    pub fn delete_todo(ctx: Context<DeleteTodo>) -> Result<()> {
-        Ok(())
-    }
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
 }
 
 #[derive(Accounts)]
-pub struct CreateProfile<'info> {
+pub struct AddComment<'info> {
     #[account(mut)]
-    pub creator: Signer<'info>,
+    pub author: Signer<'info>,
+
+    // Anyone with access to the profile can comment
+    #[account(
+        constraint = profile.authorizes(&author.key(), collaborator.as_deref(), Role::Viewer) @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = todo.profile == profile.key() @ AppError::InvalidTodo
+    )]
+    pub todo: Account<'info, Todo>,
 
     #[account(
         init,
-        payer = creator,
-        space = 8 /* account discriminator */ + Profile::SPACE,
-        seeds = [PROFILE_SEED, creator.key().as_ref()],
+        payer = author,
+        space = 8 + Comment::INIT_SPACE,
+        seeds = [COMMENT_SEED, todo.key().as_ref(), todo.comment_count.to_le_bytes().as_ref()],
         bump
     )]
-    pub profile: Account<'info, Profile>,
+    pub comment: Account<'info, Comment>,
 
     pub system_program: Program<'info, System>,
+
+    // Required when `author` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
 }
 
 #[derive(Accounts)]
-pub struct CreateTodo<'info> {
+pub struct EditComment<'info> {
+    pub user: Signer<'info>,
+
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        constraint = todo.profile == profile.key() @ AppError::InvalidTodo
+    )]
+    pub todo: Account<'info, Todo>,
+
+    // Only the author or the profile authority
+    #[account(
+        mut,
+        constraint = comment.todo == todo.key() @ AppError::InvalidComment,
+        constraint = comment.author == user.key() || profile.authority == user.key() @ AppError::InvalidAuthority
+    )]
+    pub comment: Account<'info, Comment>,
+}
+
+#[derive(Accounts)]
+pub struct DeleteComment<'info> {
+    pub user: Signer<'info>,
+
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = todo.profile == profile.key() @ AppError::InvalidTodo
+    )]
+    pub todo: Account<'info, Todo>,
+
+    // Only the author or the profile authority
+    #[account(
+        mut,
+        close = author,
+        has_one = author,
+        constraint = comment.todo == todo.key() @ AppError::InvalidComment,
+        constraint = comment.author == user.key() || profile.authority == user.key() @ AppError::InvalidAuthority
+    )]
+    pub comment: Account<'info, Comment>,
+
     #[account(mut)]
-    creator: Signer<'info>,
+    pub author: SystemAccount<'info>,
+}
 
-    #[account(mut, 
-        // has_one = authority
-        constraint = profile.authority == creator.key() @ AppError::InvalidAuthority
+#[derive(Accounts)]
+pub struct DeleteComments<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Admin) @ AppError::InvalidAuthority
     )]
-    profile: Account<'info, Profile>,
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = todo.profile == profile.key() @ AppError::InvalidTodo
+    )]
+    pub todo: Account<'info, Todo>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTagIndex<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
 
+    // Starts empty and grows as tags are added
     #[account(
         init,
-        payer = creator,
-        space = 8 + Todo::INIT_SPACE,
-        seeds = [TODO_SEED, profile.key().as_ref(), profile.todo_count.to_le_bytes().as_ref()],
+        payer = user,
+        space = 8 + 32 + 4,
+        seeds = [TAG_INDEX_SEED, profile.key().as_ref()],
         bump
     )]
-    todo: Account<'info, Todo>,
+    pub tag_index: Account<'info, TagIndex>,
 
-    system_program: Program<'info, System>,
+    pub system_program: Program<'info, System>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
 }
 
+// Too large for `init`: the client creates `book` with
+// `SystemProgram.createAccount` (8 + `TodoBook::SPACE` bytes, owned by this
+// program) in the same transaction.
 #[derive(Accounts)]
-pub struct ToggleTodo<'info> {
+pub struct CreateTodoBook<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(zero)]
+    pub book: AccountLoader<'info, TodoBook>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+// Shared by `add_book_todo`, `toggle_book_todo` and `remove_book_todo`
+#[derive(Accounts)]
+pub struct UpdateTodoBook<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        mut,
+        constraint = book.load()?.profile == profile.key() @ AppError::InvalidTodoBook
+    )]
+    pub book: AccountLoader<'info, TodoBook>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTodoBook<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Admin) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    // Rent always goes back to the profile owner, even when a collaborator closes
+    #[account(mut, address = profile.authority @ AppError::InvalidAuthority)]
+    pub authority: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        close = authority,
+        constraint = book.load()?.profile == profile.key() @ AppError::InvalidTodoBook
+    )]
+    pub book: AccountLoader<'info, TodoBook>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSubtask<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
-    
+
+    #[account(
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    // A completed parent can't end up with open subtasks
     #[account(
         mut,
-        constraint = profile.authority == user.key() @ AppError::InvalidAuthority
+        constraint = todo.profile == profile.key() @ AppError::InvalidTodo,
+        constraint = !todo.completed @ AppError::InvalidTodo
+    )]
+    pub todo: Account<'info, Todo>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Subtask::INIT_SPACE,
+        seeds = [SUBTASK_SEED, todo.key().as_ref(), todo.subtask_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub subtask: Account<'info, Subtask>,
+
+    pub system_program: Program<'info, System>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct ToggleSubtask<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Editor) @ AppError::InvalidAuthority
     )]
     pub profile: Account<'info, Profile>,
-    
+
+    // A completed parent can't end up with open subtasks
     #[account(
         mut,
-        constraint = todo.profile == profile.key() @ AppError::InvalidAuthority
+        constraint = todo.profile == profile.key() @ AppError::InvalidTodo,
+        constraint = !todo.completed @ AppError::InvalidTodo
     )]
     pub todo: Account<'info, Todo>,
+
+    #[account(
+        mut,
+        constraint = subtask.todo == todo.key() @ AppError::InvalidSubtask
+    )]
+    pub subtask: Account<'info, Subtask>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
 }
 
 #[derive(Accounts)]
-pub struct DeleteTodo<'info> {
+pub struct DeleteSubtask<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Admin) @ AppError::InvalidAuthority
+    )]
+    pub profile: Account<'info, Profile>,
+
+    // Rent always goes back to the profile owner, even when a collaborator deletes
+    #[account(mut, address = profile.authority @ AppError::InvalidAuthority)]
+    pub authority: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = todo.profile == profile.key() @ AppError::InvalidTodo
+    )]
+    pub todo: Account<'info, Todo>,
+
+    #[account(
+        mut,
+        close = authority,
+        constraint = subtask.todo == todo.key() @ AppError::InvalidSubtask
+    )]
+    pub subtask: Account<'info, Subtask>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateArchive<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
-    
+
     #[account(
         mut,
-        constraint = profile.authority == user.key() @ AppError::InvalidAuthority
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Admin) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
     )]
     pub profile: Account<'info, Profile>,
-    
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Archive::space(Archive::CHUNK_LEN),
+        seeds = [ARCHIVE_SEED, profile.key().as_ref(), profile.archive_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub archive: Account<'info, Archive>,
+
+    pub system_program: Program<'info, System>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct ArchiveTodo<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
     #[account(
         mut,
-        close = user, // This closes the account and returns rent to user
-        constraint = todo.profile == profile.key() @ AppError::InvalidAuthority
+        constraint = profile.authorizes(&user.key(), collaborator.as_deref(), Role::Admin) @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
+
+    // Rent always goes back to the profile owner, even when a collaborator archives
+    #[account(mut, address = profile.authority @ AppError::InvalidAuthority)]
+    pub authority: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        close = authority,
+        constraint = todo.profile == profile.key() @ AppError::InvalidAuthority,
+        constraint = todo.completed @ AppError::TodoNotCompleted,
+        constraint = todo.bounty.is_none() @ AppError::BountyOpen,
+        constraint = todo.commitment.is_none() @ AppError::CommitmentOpen,
+        constraint = todo.active_subtask_count == 0 @ AppError::TodoHasSubtasks,
+        constraint = todo.active_comment_count == 0 @ AppError::TodoHasComments
     )]
     pub todo: Account<'info, Todo>,
+
+    #[account(
+        mut,
+        constraint = archive.profile == profile.key() @ AppError::InvalidArchive,
+        constraint = archive.page + 1 == profile.archive_count @ AppError::InvalidArchive
+    )]
+    pub archive: Account<'info, Archive>,
+
+    // The list the todo belongs to, if it was created under one
+    #[account(mut)]
+    pub list: Option<Account<'info, TodoList>>,
+
+    // Required when the todo has tags
+    #[account(
+        mut,
+        seeds = [TAG_INDEX_SEED, profile.key().as_ref()],
+        bump
+    )]
+    pub tag_index: Option<Account<'info, TagIndex>>,
+
+    pub system_program: Program<'info, System>,
+
+    // Required when `user` is not the profile authority
+    pub collaborator: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseProfile<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = profile.authority == user.key() @ AppError::InvalidAuthority,
+        constraint = profile.version == Profile::VERSION @ AppError::ProfileNotMigrated
+    )]
+    pub profile: Account<'info, Profile>,
 }
@@ -1,21 +1,331 @@
 use anchor_lang::prelude::*;
 
+use crate::constant::INTEGRATION_AUTHORITY_SEED;
+
 #[account]
 pub struct Profile {
     pub key: Pubkey,
 
     pub name: String, // max len = 100
 
+    // Starts as the creator, whose key the PDA stays seeded by
     pub authority: Pubkey,
 
-    pub todo_count: u8,
+    // Todos created directly under the profile, before lists existed.
+    // Was a `u8` in version 0. A little-endian `u8` followed by the zeroed
+    // tail of the account reads back as the same `u32`.
+    pub todo_count: u32,
+
+    pub version: u8,
+
+    // Todos created and not yet deleted, across all lists
+    pub active_todo_count: u32,
+
+    pub list_count: u32,
+
+    // Lists created and not yet deleted
+    pub active_list_count: u32,
+
+    pub collaborator_count: u32,
+
+    // `Handle` PDA currently claimed by this profile
+    pub handle: Option<Pubkey>,
+
+    // Proposed by `authority`, takes over once it signs `accept_authority`
+    pub pending_authority: Option<Pubkey>,
+
+    // Public key clients encrypt private todos to. Only the key is on-chain.
+    pub encryption_key: Option<[u8; 32]>,
+
+    // Bumped on every `set_encryption_key`, recorded in each encrypted todo
+    pub key_version: u32,
+
+    // Archive pages created and not closed yet; todos are archived to the
+    // last one
+    pub archive_count: u32,
+
+    // Todos not completed yet, including todo book entries
+    pub open_count: u32,
+
+    // Completed todos, including archived ones
+    pub completed_count: u32,
+
+    // Todos removed by `delete_todo`, `remove_book_todo` or `close_profile`
+    pub deleted_count: u32,
+
+    // Days since the Unix epoch
+    pub last_completed_day: i64,
+
+    // Consecutive days, up to `last_completed_day`, with a completion
+    pub current_streak: u32,
+
+    // Todo indices `migrate_profile` has counted so far, for version 0
+    // profiles
+    pub legacy_todos_checked: u32,
+
+    // Whether the `TagIndex` PDA exists; it has to be closed with the profile
+    pub has_tag_index: bool,
+
+    // `TodoBook`s not closed yet. They aren't PDAs, so only this count keeps
+    // the profile from closing without them.
+    pub todo_book_count: u32,
+
+    // Session keys not revoked yet. They'd sign for a re-created profile at
+    // the same address, so the profile can't close while any remain.
+    pub session_key_count: u32,
+
+    // Approved integrations, which likewise outlive a closed profile
+    pub integration_count: u32,
 }
 
 impl Profile {
+    pub const VERSION: u8 = 10;
+
+    pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
     pub const SPACE: usize = 32 + // key
                             (4 + 100) // name
                             + 32 // authority
-                            + 1; // todo_count
+                            + 4 // todo_count
+                            + 1 // version
+                            + 4 // active_todo_count
+                            + 4 // list_count
+                            + 4 // active_list_count
+                            + 4 // collaborator_count
+                            + (1 + 32) // handle
+                            + (1 + 32) // pending_authority
+                            + (1 + 32) // encryption_key
+                            + 4 // key_version
+                            + 4 // archive_count
+                            + 4 // open_count
+                            + 4 // completed_count
+                            + 4 // deleted_count
+                            + 8 // last_completed_day
+                            + 4 // current_streak
+                            + 4 // legacy_todos_checked
+                            + 1 // has_tag_index
+                            + 4 // todo_book_count
+                            + 4 // session_key_count
+                            + 4; // integration_count
+
+    // Counts saturate: migrated profiles start at zero with todos already
+    // open or completed.
+
+    pub fn record_created(&mut self) {
+        self.open_count += 1;
+    }
+
+    /// Also extends the daily streak, or restarts it after a missed day.
+    pub fn record_completed(&mut self, now: i64) {
+        self.open_count = self.open_count.saturating_sub(1);
+        self.completed_count += 1;
+
+        let day = now.div_euclid(Self::SECONDS_PER_DAY);
+        if day == self.last_completed_day + 1 {
+            self.current_streak += 1;
+        } else if day != self.last_completed_day {
+            self.current_streak = 1;
+        }
+        self.last_completed_day = day;
+    }
+
+    pub fn record_reopened(&mut self) {
+        self.completed_count = self.completed_count.saturating_sub(1);
+        self.open_count += 1;
+    }
+
+    pub fn record_deleted(&mut self, completed: bool) {
+        if completed {
+            self.completed_count = self.completed_count.saturating_sub(1);
+        } else {
+            self.open_count = self.open_count.saturating_sub(1);
+        }
+        self.deleted_count += 1;
+    }
+
+    /// Whether `signer` may act on this profile with at least `role`, either as
+    /// its authority or through a `Collaborator` record.
+    pub fn authorizes(
+        &self,
+        signer: &Pubkey,
+        collaborator: Option<&Collaborator>,
+        role: Role,
+    ) -> bool {
+        if self.authority == *signer {
+            return true;
+        }
+
+        collaborator.is_some_and(|collaborator| {
+            collaborator.profile == self.key
+                && collaborator.member == *signer
+                && collaborator.role >= role
+        })
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Collaborator {
+    pub profile: Pubkey,
+
+    pub member: Pubkey,
+
+    pub role: Role,
+}
+
+/// Temporary keypair the authority lets create or toggle todos without the
+/// main wallet signing.
+#[account]
+#[derive(InitSpace)]
+pub struct SessionKey {
+    pub profile: Pubkey,
+
+    pub signer: Pubkey,
+
+    pub expires_at: i64,
+
+    pub scope: SessionScope,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct SessionScope {
+    pub create_todo: bool,
+
+    pub toggle_todo: bool,
+}
+
+impl SessionKey {
+    /// Whether `signer` may use this unexpired session on `profile`. Scope is
+    /// checked by the caller.
+    pub fn is_valid(&self, profile: &Pubkey, signer: &Pubkey) -> bool {
+        self.profile == *profile
+            && self.signer == *signer
+            && Clock::get().is_ok_and(|clock| clock.unix_timestamp < self.expires_at)
+    }
+}
+
+/// The profile authority's approval for `program` to create todos by CPI.
+#[account]
+#[derive(InitSpace)]
+pub struct Integration {
+    pub profile: Pubkey,
+
+    pub program: Pubkey,
+}
+
+impl Integration {
+    /// PDA that `program` signs `create_todo_via_cpi` with. Only `program`
+    /// itself can produce that signature.
+    pub fn caller_authority(program: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[INTEGRATION_AUTHORITY_SEED], program).0
+    }
+}
+
+/// Unique, lower-cased name pointing at the profile that claimed it.
+#[account]
+#[derive(InitSpace)]
+pub struct Handle {
+    pub profile: Pubkey,
+
+    #[max_len(32)]
+    pub name: String,
+}
+
+impl Handle {
+    pub const MIN_LEN: usize = 3;
+
+    pub const MAX_LEN: usize = 32;
+
+    /// Handles are seeded by their lower-cased form, so "Alice" and "alice"
+    /// resolve to the same account.
+    pub fn normalize(name: &str) -> String {
+        name.to_ascii_lowercase()
+    }
+
+    /// The normalized name capped at `MAX_LEN` bytes, the longest a seed can
+    /// be. `init` derives the address before the handler runs, so an overlong
+    /// name must reach its `is_valid` check rather than fail the derivation.
+    pub fn seed(name: &str) -> Vec<u8> {
+        let mut seed = Self::normalize(name).into_bytes();
+        seed.truncate(Self::MAX_LEN);
+        seed
+    }
+
+    pub fn is_valid(name: &str) -> bool {
+        (Self::MIN_LEN..=Self::MAX_LEN).contains(&name.len())
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Bounty {
+    pub todo: Pubkey,
+
+    // Funded the bounty and gets it back on cancel
+    pub creator: Pubkey,
+
+    pub reviewer: Pubkey,
+
+    pub assignee: Option<Pubkey>,
+
+    // `None` for SOL bounties, which are held in this account's lamports
+    pub mint: Option<Pubkey>,
+
+    pub amount: u64,
+
+    pub bump: u8,
+}
+
+// Ordered by rights: each role can do everything the previous one can
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, InitSpace)]
+pub enum Role {
+    Viewer,
+    Editor,
+    Admin,
+}
+
+/// Lamports staked on completing a todo by `deadline`. The stake is held in
+/// this account's lamports, on top of its rent.
+#[account]
+#[derive(InitSpace)]
+pub struct Commitment {
+    pub todo: Pubkey,
+
+    // Locked the stake and gets it back if the todo is done in time
+    pub staker: Pubkey,
+
+    // Receives the stake once the deadline is missed
+    pub beneficiary: Pubkey,
+
+    pub amount: u64,
+
+    pub deadline: i64,
+}
+
+impl Commitment {
+    pub fn is_met(&self, todo: &Todo) -> bool {
+        todo.completed
+            && todo
+                .completed_at
+                .is_some_and(|completed_at| completed_at <= self.deadline)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TodoList {
+    pub profile: Pubkey,
+
+    #[max_len(100)]
+    pub name: String,
+
+    pub todo_count: u32,
+
+    // Todos created and not yet deleted
+    pub active_todo_count: u32,
 }
 
 #[account]
@@ -27,4 +337,377 @@ pub struct Todo {
     pub content: String,
 
     pub completed: bool,
+
+    // `Pubkey::default()` for todos created directly under the profile
+    pub list: Pubkey,
+
+    pub due_at: Option<i64>,
+
+    pub priority: Priority,
+
+    pub created_at: i64,
+
+    pub completed_at: Option<i64>,
+
+    // Open bounty escrowed against this todo, if any
+    pub bounty: Option<Pubkey>,
+
+    pub subtask_count: u32,
+
+    // Subtasks created and not yet deleted
+    pub active_subtask_count: u32,
+
+    pub completed_subtask_count: u32,
+
+    #[max_len(4, 16)]
+    pub tags: Vec<String>,
+
+    // Indices of todos under the same list (or profile, for legacy todos)
+    // that must be completed first
+    #[max_len(4)]
+    pub prerequisites: Vec<u32>,
+
+    // Stake locked against this todo's deadline, if any
+    pub commitment: Option<Pubkey>,
+
+    // Program that created the todo through `create_todo_via_cpi`
+    pub created_by: Option<Pubkey>,
+
+    // Set for private todos, whose `content` is left empty
+    pub encrypted: Option<EncryptedContent>,
+
+    pub recurrence: Option<Recurrence>,
+
+    // Occurrence this todo was spawned from when it was completed
+    pub previous_occurrence: Option<Pubkey>,
+
+    // Spawned when this todo was first completed; toggling again doesn't
+    // spawn another
+    pub next_occurrence: Option<Pubkey>,
+
+    pub comment_count: u32,
+
+    // Comments added and not yet deleted
+    pub active_comment_count: u32,
+
+    pub content_mode: ContentMode,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum ContentMode {
+    // `content` is the todo itself
+    Inline,
+    // `content` is a URI to the full body, which hashes (SHA-256) to `hash`
+    External { hash: [u8; 32] },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    EverySeconds(u32),
+}
+
+impl Recurrence {
+    pub fn interval(&self) -> i64 {
+        match self {
+            Recurrence::Daily => 24 * 60 * 60,
+            Recurrence::Weekly => 7 * 24 * 60 * 60,
+            Recurrence::EverySeconds(seconds) => *seconds as i64,
+        }
+    }
+
+    /// First due date after `now`, stepping from `due_at` by whole intervals,
+    /// so a todo completed late doesn't spawn one that is already overdue.
+    /// `None` if that date doesn't fit in an `i64`.
+    pub fn next_due(&self, due_at: i64, now: i64) -> Option<i64> {
+        let interval = self.interval();
+        let periods = (now.checked_sub(due_at)? / interval)
+            .checked_add(1)?
+            .max(1);
+        interval.checked_mul(periods)?.checked_add(due_at)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct EncryptedContent {
+    pub algorithm: EncryptionAlgorithm,
+
+    // AES-GCM only uses the first 12 bytes
+    pub nonce: [u8; 24],
+
+    // `Profile::key_version` of the key the content was encrypted to
+    pub key_version: u32,
+
+    #[max_len(216)]
+    pub ciphertext: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum EncryptionAlgorithm {
+    XChaCha20Poly1305,
+    Aes256Gcm,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Comment {
+    pub todo: Pubkey,
+
+    // Paid the rent and gets it back on delete
+    pub author: Pubkey,
+
+    #[max_len(280)]
+    pub body: String,
+
+    pub created_at: i64,
+
+    pub edited_at: Option<i64>,
+}
+
+impl Comment {
+    pub const MAX_BODY_LEN: usize = 280;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Subtask {
+    pub todo: Pubkey,
+
+    pub profile: Pubkey,
+
+    #[max_len(100)]
+    pub content: String,
+
+    pub completed: bool,
+}
+
+impl Subtask {
+    pub const MAX_CONTENT_LEN: usize = 100;
+}
+
+/// Which todos of a profile carry each tag, so a client can fetch every todo
+/// with a given tag from one account read.
+#[account]
+pub struct TagIndex {
+    pub profile: Pubkey,
+
+    pub entries: Vec<TagEntry>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TagEntry {
+    pub tag: String,
+
+    pub todos: Vec<Pubkey>,
+}
+
+impl TagIndex {
+    /// Space (without discriminator) the index currently serializes to.
+    pub fn space(&self) -> usize {
+        32 + 4
+            + self
+                .entries
+                .iter()
+                .map(|entry| 4 + entry.tag.len() + 4 + 32 * entry.todos.len())
+                .sum::<usize>()
+    }
+
+    pub fn add(&mut self, tag: &str, todo: Pubkey) {
+        match self.entries.iter_mut().find(|entry| entry.tag == tag) {
+            Some(entry) => entry.todos.push(todo),
+            None => self.entries.push(TagEntry {
+                tag: tag.to_string(),
+                todos: vec![todo],
+            }),
+        }
+    }
+
+    // Tags nobody uses any more are dropped
+    pub fn remove(&mut self, tag: &str, todo: Pubkey) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.tag == tag) {
+            entry.todos.retain(|key| *key != todo);
+        }
+        self.entries.retain(|entry| !entry.todos.is_empty());
+    }
+}
+
+/// Up to `CAPACITY` short todos packed into one zero-copy account, for users
+/// who don't need a `Todo` account (and its rent) per entry.
+#[account(zero_copy)]
+pub struct TodoBook {
+    pub profile: Pubkey,
+
+    // Free-slot bitmap: bit `i` is set while `entries[i]` holds a todo
+    pub used_slots: [u64; TodoBook::CAPACITY / 64],
+
+    pub todo_count: u32,
+
+    pub _reserved: [u8; 4],
+
+    pub entries: [TodoEntry; TodoBook::CAPACITY],
+}
+
+#[zero_copy]
+pub struct TodoEntry {
+    pub created_at: i64,
+
+    // 0 while the todo is open
+    pub completed_at: i64,
+
+    pub content: [u8; TodoBook::MAX_CONTENT_LEN],
+
+    pub content_len: u8,
+
+    pub completed: u8,
+
+    // `Priority` as `u8`
+    pub priority: u8,
+
+    pub _reserved: [u8; 5],
+}
+
+impl TodoBook {
+    pub const CAPACITY: usize = 256;
+
+    pub const MAX_CONTENT_LEN: usize = 64;
+
+    pub const SPACE: usize = std::mem::size_of::<TodoBook>();
+
+    pub fn is_used(&self, slot: usize) -> bool {
+        slot < Self::CAPACITY && self.used_slots[slot / 64] & (1 << (slot % 64)) != 0
+    }
+
+    pub fn free_slot(&self) -> Option<usize> {
+        self.used_slots
+            .iter()
+            .enumerate()
+            .find(|(_, word)| **word != u64::MAX)
+            .map(|(i, word)| i * 64 + word.trailing_ones() as usize)
+    }
+
+    /// Entries currently holding a todo.
+    pub fn used_entries(&self) -> impl Iterator<Item = &TodoEntry> {
+        (0..Self::CAPACITY)
+            .filter(|slot| self.is_used(*slot))
+            .map(|slot| &self.entries[slot])
+    }
+
+    pub fn set_used(&mut self, slot: usize, used: bool) {
+        if used {
+            self.used_slots[slot / 64] |= 1 << (slot % 64);
+        } else {
+            self.used_slots[slot / 64] &= !(1 << (slot % 64));
+        }
+    }
+}
+
+/// Append-only log of archived todos. A profile can have any number of pages,
+/// each holding up to `MAX_RECORDS`.
+#[account]
+pub struct Archive {
+    pub profile: Pubkey,
+
+    pub page: u32,
+
+    pub records: Vec<ArchiveRecord>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ArchiveRecord {
+    pub list: Pubkey,
+
+    pub index: u32,
+
+    // SHA-256 of the content, or of the ciphertext for encrypted todos
+    pub content_hash: [u8; 32],
+
+    pub completed_at: i64,
+}
+
+impl Archive {
+    pub const RECORD_SPACE: usize = 32 + 4 + 32 + 8;
+
+    // Records the account grows by at a time
+    pub const CHUNK_LEN: usize = 32;
+
+    pub const MAX_RECORDS: usize = 512;
+
+    /// Space (without discriminator) for a page with room for `capacity` records.
+    pub fn space(capacity: usize) -> usize {
+        32 + 4 + 4 + capacity * Self::RECORD_SPACE
+    }
+}
+
+// `Low` first so todos from before priorities existed read back as `Low`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Todo {
+    pub const MAX_CONTENT_LEN: usize = 200;
+
+    pub const MAX_TAGS: usize = 4;
+
+    pub const MAX_TAG_LEN: usize = 16;
+
+    pub const MAX_PREREQUISITES: usize = 4;
+
+    pub const URI_SCHEMES: [&'static str; 3] = ["https://", "ipfs://", "ar://"];
+
+    // Room for the longest content plus an AEAD tag
+    pub const MAX_CIPHERTEXT_LEN: usize = Todo::MAX_CONTENT_LEN + 16;
+
+    /// Space (without discriminator) needed for a todo whose content is
+    /// `content_len` bytes, or whose encrypted content is `ciphertext_len`.
+    pub fn space(content_len: usize, ciphertext_len: usize) -> usize {
+        Todo::INIT_SPACE - Todo::MAX_CONTENT_LEN - Todo::MAX_CIPHERTEXT_LEN
+            + content_len
+            + ciphertext_len
+    }
+
+    /// URIs share `content` and its length limit.
+    pub fn uri_valid(uri: &str) -> bool {
+        uri.len() <= Self::MAX_CONTENT_LEN
+            && Self::URI_SCHEMES
+                .iter()
+                .any(|scheme| uri.len() > scheme.len() && uri.starts_with(scheme))
+    }
+
+    pub fn tags_valid(tags: &[String]) -> bool {
+        tags.len() <= Self::MAX_TAGS
+            && tags.iter().enumerate().all(|(i, tag)| {
+                (1..=Self::MAX_TAG_LEN).contains(&tag.len()) && !tags[..i].contains(tag)
+            })
+    }
+
+    /// Key the todo's index is seeded under: its list, or the profile for
+    /// todos created before lists existed.
+    pub fn seed_parent(&self) -> Pubkey {
+        if self.list == Pubkey::default() {
+            self.profile
+        } else {
+            self.list
+        }
+    }
+
+    pub fn has_open_subtasks(&self) -> bool {
+        self.completed_subtask_count < self.active_subtask_count
+    }
+
+    pub fn is_overdue(&self, now: i64) -> bool {
+        !self.completed && self.due_at.is_some_and(|due_at| due_at < now)
+    }
+
+    /// Seed bytes for the todo at `index`. Indices below 256 use a single byte,
+    /// matching the seeds todos got while `Profile::todo_count` was a `u8`.
+    pub fn index_seed(index: u32) -> Vec<u8> {
+        match u8::try_from(index) {
+            Ok(index) => vec![index],
+            Err(_) => index.to_le_bytes().to_vec(),
+        }
+    }
 }
@@ -0,0 +1,501 @@
+//! Minimal in-process bank for driving the program from `cargo test`.
+//!
+//! Instructions are serialized into the same input buffer the loader hands an
+//! on-chain program and run through `todo_app::entry`. CPIs land in
+//! `SyscallStubs::sol_invoke_signed`, which runs the callee natively: the
+//! system program instructions Anchor's `init` and `realloc` use, and the
+//! SPL Token and Associated Token Account programs through their own
+//! processors. Return data is passed between them as the runtime does.
+//!
+//! Before and after every CPI and at the end of the instruction, account
+//! changes are checked against the runtime's ownership rules: read-only
+//! accounts stay unchanged, and only an account's owner may debit it, modify
+//! its data or reassign it (the latter only with zeroed data). A CPI may not
+//! make an account writable or a signer unless the caller holds that privilege
+//! or, for signers, the seeds of a PDA of the caller. Once the instruction
+//! succeeds, no writable account may be left rent-paying unless it already
+//! was, with the same size and no more lamports. A violation panics rather
+//! than returning the runtime's error. Not modelled: compute and stack limits,
+//! and any program other than the three above.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    bpf_loader, bpf_loader_upgradeable,
+    entrypoint::{self, ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    program_stubs::{self, SyscallStubs},
+    system_program,
+};
+use anchor_lang::{InstructionData, ToAccountMetas};
+
+pub const UNIX_TIMESTAMP: i64 = 1_700_000_000;
+
+thread_local! {
+    // Per thread, since tests run in parallel against the same stubs
+    static NOW: Cell<i64> = const { Cell::new(UNIX_TIMESTAMP) };
+
+    // Accounts of the running instruction as of the last ownership check
+    static SNAPSHOT: RefCell<Vec<(Pubkey, bool, AccountState)>> = const { RefCell::new(Vec::new()) };
+
+    // Programs on the invocation stack, innermost last
+    static CALLERS: RefCell<Vec<Pubkey>> = const { RefCell::new(Vec::new()) };
+
+    static RETURN_DATA: RefCell<Option<(Pubkey, Vec<u8>)>> = const { RefCell::new(None) };
+}
+
+const NON_DUP_MARKER: u8 = u8::MAX;
+
+#[derive(Clone)]
+pub struct AccountState {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+pub struct Bank {
+    accounts: HashMap<Pubkey, AccountState>,
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(Stubs));
+        });
+        NOW.with(|now| now.set(UNIX_TIMESTAMP));
+
+        let mut accounts = HashMap::new();
+        for (program, loader) in [
+            (todo_app::ID, bpf_loader_upgradeable::ID),
+            (system_program::ID, Pubkey::default()),
+            (spl_token::ID, bpf_loader::ID),
+            (spl_associated_token_account::ID, bpf_loader::ID),
+        ] {
+            accounts.insert(
+                program,
+                AccountState {
+                    lamports: 1,
+                    data: Vec::new(),
+                    owner: loader,
+                    executable: true,
+                },
+            );
+        }
+
+        Self { accounts }
+    }
+
+    /// A new system account holding `lamports`.
+    pub fn wallet(&mut self, lamports: u64) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.accounts.insert(
+            key,
+            AccountState {
+                lamports,
+                data: Vec::new(),
+                owner: system_program::ID,
+                executable: false,
+            },
+        );
+        key
+    }
+
+    /// A new SPL Token mint with `decimals` and no supply.
+    pub fn mint(&mut self, authority: Pubkey, decimals: u8) -> Pubkey {
+        let mint = spl_token::state::Mint {
+            mint_authority: COption::Some(authority),
+            decimals,
+            is_initialized: true,
+            ..Default::default()
+        };
+        self.token_state(mint)
+    }
+
+    /// A new SPL Token account of `owner` holding `amount` of `mint`.
+    pub fn token_account(&mut self, mint: Pubkey, owner: Pubkey, amount: u64) -> Pubkey {
+        let account = spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        self.token_state(account)
+    }
+
+    fn token_state<T: Pack>(&mut self, state: T) -> Pubkey {
+        let key = Pubkey::new_unique();
+        let mut data = vec![0; T::LEN];
+        state.pack_into_slice(&mut data);
+        self.accounts.insert(
+            key,
+            AccountState {
+                lamports: Rent::default().minimum_balance(T::LEN),
+                data,
+                owner: spl_token::ID,
+                executable: false,
+            },
+        );
+        key
+    }
+
+    /// Moves the clock of every bank on this thread to `unix_timestamp`.
+    pub fn warp(&mut self, unix_timestamp: i64) {
+        NOW.with(|now| now.set(unix_timestamp));
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.accounts.get(key).map_or(0, |account| account.lamports)
+    }
+
+    /// The amount held by SPL Token account `key`.
+    pub fn token_balance(&self, key: &Pubkey) -> u64 {
+        let account = self.accounts.get(key).expect("account not found");
+        spl_token::state::Account::unpack(&account.data)
+            .expect("invalid token account")
+            .amount
+    }
+
+    pub fn get(&self, key: &Pubkey) -> Option<&AccountState> {
+        self.accounts.get(key)
+    }
+
+    pub fn set(&mut self, key: Pubkey, account: AccountState) {
+        self.accounts.insert(key, account);
+    }
+
+    pub fn account<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self.accounts.get(key).expect("account not found");
+        T::try_deserialize(&mut account.data.as_slice()).expect("invalid account data")
+    }
+
+    /// Runs one instruction. Account changes are kept only if it succeeds.
+    pub fn process(
+        &mut self,
+        accounts: impl ToAccountMetas,
+        args: impl InstructionData,
+    ) -> std::result::Result<(), ProgramError> {
+        self.process_instruction(Instruction {
+            program_id: todo_app::ID,
+            accounts: accounts.to_account_metas(None),
+            data: args.data(),
+        })
+    }
+
+    fn state(&self, key: &Pubkey) -> AccountState {
+        self.accounts.get(key).cloned().unwrap_or(AccountState {
+            lamports: 0,
+            data: Vec::new(),
+            owner: system_program::ID,
+            executable: false,
+        })
+    }
+
+    pub fn process_instruction(
+        &mut self,
+        instruction: Instruction,
+    ) -> std::result::Result<(), ProgramError> {
+        // Signer and writable flags are merged across duplicate metas
+        let mut keys: Vec<(Pubkey, bool, bool)> = Vec::new();
+        for meta in &instruction.accounts {
+            match keys.iter_mut().find(|(key, _, _)| *key == meta.pubkey) {
+                Some((_, is_signer, is_writable)) => {
+                    *is_signer |= meta.is_signer;
+                    *is_writable |= meta.is_writable;
+                }
+                None => keys.push((meta.pubkey, meta.is_signer, meta.is_writable)),
+            }
+        }
+
+        let mut input = Vec::new();
+        input.extend_from_slice(&(instruction.accounts.len() as u64).to_le_bytes());
+        for (position, meta) in instruction.accounts.iter().enumerate() {
+            let first = instruction.accounts[..position]
+                .iter()
+                .position(|earlier| earlier.pubkey == meta.pubkey);
+            if let Some(first) = first {
+                input.push(first as u8);
+                input.extend_from_slice(&[0; 7]);
+                continue;
+            }
+
+            let (key, is_signer, is_writable) = keys
+                .iter()
+                .find(|(key, _, _)| *key == meta.pubkey)
+                .copied()
+                .unwrap();
+            let account = self.state(&key);
+
+            input.push(NON_DUP_MARKER);
+            input.push(is_signer as u8);
+            input.push(is_writable as u8);
+            input.push(account.executable as u8);
+            input.extend_from_slice(&[0; 4]);
+            input.extend_from_slice(key.as_ref());
+            input.extend_from_slice(account.owner.as_ref());
+            input.extend_from_slice(&account.lamports.to_le_bytes());
+            input.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            input.extend_from_slice(&account.data);
+            input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            input.resize(input.len().next_multiple_of(8), 0);
+            input.extend_from_slice(&u64::MAX.to_le_bytes());
+        }
+        input.extend_from_slice(&(instruction.data.len() as u64).to_le_bytes());
+        input.extend_from_slice(&instruction.data);
+        input.extend_from_slice(instruction.program_id.as_ref());
+
+        // `deserialize` expects the buffer to be 8-byte aligned
+        let mut buffer = vec![0u64; input.len().div_ceil(8)];
+        bytemuck::cast_slice_mut::<u64, u8>(&mut buffer)[..input.len()].copy_from_slice(&input);
+
+        SNAPSHOT.with(|snapshot| {
+            *snapshot.borrow_mut() = keys
+                .iter()
+                .map(|(key, _, is_writable)| (*key, *is_writable, self.state(key)))
+                .collect();
+        });
+        CALLERS.with(|callers| *callers.borrow_mut() = vec![instruction.program_id]);
+        RETURN_DATA.with(|data| *data.borrow_mut() = None);
+
+        let (program_id, infos, data) =
+            unsafe { entrypoint::deserialize(buffer.as_mut_ptr() as *mut u8) };
+        todo_app::entry(program_id, &infos, data)?;
+        verify_changes(&todo_app::ID, &infos);
+
+        let lamports_before: u64 = keys.iter().map(|(key, _, _)| self.lamports(key)).sum();
+        let mut lamports_after = 0;
+        for (key, _, is_writable) in &keys {
+            let info = infos.iter().find(|info| info.key == key).unwrap();
+            lamports_after += info.lamports();
+            if !is_writable {
+                continue;
+            }
+            verify_rent_state(key, &self.state(key), info);
+        }
+        assert_eq!(
+            lamports_before, lamports_after,
+            "lamports were not balanced"
+        );
+
+        for (key, _, is_writable) in &keys {
+            let info = infos.iter().find(|info| info.key == key).unwrap();
+            if !is_writable {
+                continue;
+            }
+            if info.lamports() == 0 {
+                self.accounts.remove(key);
+            } else {
+                self.accounts.insert(
+                    *key,
+                    AccountState {
+                        lamports: info.lamports(),
+                        data: info.data.borrow().to_vec(),
+                        owner: *info.owner,
+                        executable: info.executable,
+                    },
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let caller = CALLERS.with(|callers| *callers.borrow().last().unwrap());
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        // The callee sees the privileges of the instruction, not the caller's
+        let mut accounts = Vec::new();
+        for meta in &instruction.accounts {
+            let info = account_infos
+                .iter()
+                .find(|info| *info.key == meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if meta.is_signer && !info.is_signer && !signers.contains(info.key) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            assert!(
+                !meta.is_writable || info.is_writable,
+                "{caller} escalated {} to writable",
+                info.key
+            );
+
+            let mut account = info.clone();
+            account.is_signer = meta.is_signer;
+            account.is_writable = meta.is_writable;
+            accounts.push(account);
+        }
+        verify_changes(&caller, account_infos);
+
+        let program_id = instruction.program_id;
+        CALLERS.with(|callers| callers.borrow_mut().push(program_id));
+        RETURN_DATA.with(|data| *data.borrow_mut() = None);
+        let result = match program_id {
+            system_program::ID => process_system(&accounts, &instruction.data),
+            spl_token::ID => {
+                spl_token::processor::Processor::process(&program_id, &accounts, &instruction.data)
+            }
+            spl_associated_token_account::ID => {
+                spl_associated_token_account::processor::process_instruction(
+                    &program_id,
+                    &accounts,
+                    &instruction.data,
+                )
+            }
+            _ => Err(ProgramError::IncorrectProgramId),
+        };
+        CALLERS.with(|callers| callers.borrow_mut().pop());
+        result?;
+        verify_changes(&program_id, account_infos);
+
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            slot: 1,
+            unix_timestamp: NOW.with(Cell::get),
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        entrypoint::SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        entrypoint::SUCCESS
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        RETURN_DATA.with(|data| data.borrow().clone())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let program = CALLERS.with(|callers| *callers.borrow().last().unwrap());
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = Some((program, data.to_vec())));
+    }
+}
+
+// The system program instructions used by Anchor and the token programs
+fn process_system(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let u64_at = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+    let pubkey_at = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]).unwrap();
+
+    match u32::from_le_bytes(data[..4].try_into().unwrap()) {
+        // CreateAccount { lamports, space, owner }
+        0 => {
+            let (from, to) = (&accounts[0], &accounts[1]);
+            if to.lamports() > 0 || !to.data_is_empty() {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            move_lamports(from, to, u64_at(4))?;
+            to.resize(u64_at(12) as usize)?;
+            to.assign(&pubkey_at(20));
+        }
+        // Assign { owner }
+        1 => accounts[0].assign(&pubkey_at(4)),
+        // Transfer { lamports }
+        2 => move_lamports(&accounts[0], &accounts[1], u64_at(4))?,
+        // Allocate { space }
+        8 => accounts[0].resize(u64_at(4) as usize)?,
+        _ => return Err(ProgramError::InvalidInstructionData),
+    }
+
+    Ok(())
+}
+
+/// Checks what `program` changed in `infos` since the last check against the
+/// runtime's ownership rules, then records the new state.
+fn verify_changes(program: &Pubkey, infos: &[AccountInfo]) {
+    SNAPSHOT.with(|snapshot| {
+        for (key, is_writable, before) in snapshot.borrow_mut().iter_mut() {
+            let Some(info) = infos.iter().find(|info| info.key == key) else {
+                continue;
+            };
+            let after = AccountState {
+                lamports: info.lamports(),
+                data: info.data.borrow().to_vec(),
+                owner: *info.owner,
+                executable: info.executable,
+            };
+
+            if !*is_writable {
+                assert!(
+                    after.lamports == before.lamports
+                        && after.data == before.data
+                        && after.owner == before.owner,
+                    "read-only account {key} was modified"
+                );
+            }
+            if before.owner != *program {
+                assert!(
+                    after.lamports >= before.lamports,
+                    "{program} debited {key}, which it doesn't own"
+                );
+                assert!(
+                    after.data == before.data,
+                    "{program} modified the data of {key}, which it doesn't own"
+                );
+            }
+            if after.owner != before.owner {
+                assert!(
+                    before.owner == *program && after.data.iter().all(|byte| *byte == 0),
+                    "{program} reassigned {key} without owning it or zeroing its data"
+                );
+            }
+
+            *before = after;
+        }
+    });
+}
+
+/// Checks that `info` was not left rent-paying, unless it already was with
+/// the same size and was only debited.
+fn verify_rent_state(key: &Pubkey, before: &AccountState, info: &AccountInfo) {
+    let rent = Rent::default();
+    let rent_paying = |lamports: u64, len: usize| lamports > 0 && !rent.is_exempt(lamports, len);
+    if !rent_paying(info.lamports(), info.data_len()) {
+        return;
+    }
+
+    assert!(
+        rent_paying(before.lamports, before.data.len())
+            && before.data.len() == info.data_len()
+            && info.lamports() <= before.lamports,
+        "{key} was left rent-paying"
+    );
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    let remaining = from
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    **from.try_borrow_mut_lamports()? = remaining;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
@@ -0,0 +1,1657 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::Instruction, program_error::ProgramError, system_program,
+};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::{AccountState, Bank, UNIX_TIMESTAMP};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::error::TokenError;
+use todo_app::constant::{
+    ARCHIVE_SEED, BOUNTY_SEED, COLLABORATOR_SEED, COMMENT_SEED, COMMITMENT_SEED, HANDLE_SEED,
+    INTEGRATION_SEED, PROFILE_SEED, SESSION_KEY_SEED, SUBTASK_SEED, TAG_INDEX_SEED, TODO_LIST_SEED,
+    TODO_SEED,
+};
+use todo_app::error::AppError;
+use todo_app::state::{
+    Archive, ArchiveRecord, Bounty, Handle, Priority, Profile, Recurrence, Role, SessionScope,
+    Todo, TodoBook, TodoList,
+};
+use todo_app::{accounts, instruction};
+
+const AIRDROP: u64 = 10_000_000_000;
+
+struct Fixture {
+    bank: Bank,
+    user: Pubkey,
+    profile: Pubkey,
+    list: Pubkey,
+}
+
+fn profile_address(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[PROFILE_SEED, user.as_ref()], &todo_app::ID).0
+}
+
+fn list_address(profile: &Pubkey, index: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            TODO_LIST_SEED,
+            profile.as_ref(),
+            index.to_le_bytes().as_ref(),
+        ],
+        &todo_app::ID,
+    )
+    .0
+}
+
+fn todo_address(list: &Pubkey, index: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[TODO_SEED, list.as_ref(), Todo::index_seed(index).as_ref()],
+        &todo_app::ID,
+    )
+    .0
+}
+
+fn archive_address(profile: &Pubkey, page: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[ARCHIVE_SEED, profile.as_ref(), &page.to_le_bytes()],
+        &todo_app::ID,
+    )
+    .0
+}
+
+fn app_error(error: AppError) -> ProgramError {
+    ProgramError::Custom(error.into())
+}
+
+fn create_profile(
+    bank: &mut Bank,
+    user: Pubkey,
+    name: &str,
+) -> std::result::Result<(), ProgramError> {
+    bank.process(
+        accounts::CreateProfile {
+            creator: user,
+            profile: profile_address(&user),
+            system_program: system_program::ID,
+        },
+        instruction::CreateProfile {
+            name: name.to_string(),
+        },
+    )
+}
+
+// A profile with one empty list
+fn setup() -> Fixture {
+    let mut bank = Bank::new();
+    let user = bank.wallet(AIRDROP);
+    let profile = profile_address(&user);
+    let list = list_address(&profile, 0);
+
+    create_profile(&mut bank, user, "Alice").unwrap();
+    bank.process(
+        accounts::CreateTodoList {
+            creator: user,
+            profile,
+            list,
+            system_program: system_program::ID,
+        },
+        instruction::CreateTodoList {
+            name: "Inbox".to_string(),
+        },
+    )
+    .unwrap();
+
+    Fixture {
+        bank,
+        user,
+        profile,
+        list,
+    }
+}
+
+// A version 0 profile: `todo_count` was a `u8` and nothing followed it.
+// `todos` holds the content of each todo index still open, in the original
+// `Todo` layout sized to its content.
+fn legacy_profile(bank: &mut Bank, user: Pubkey, todos: &[Option<&str>]) -> Pubkey {
+    let profile = profile_address(&user);
+
+    let mut data = Profile::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(profile.as_ref());
+    "Alice".to_string().serialize(&mut data).unwrap();
+    data.extend_from_slice(user.as_ref());
+    data.push(todos.len() as u8);
+    data.resize(8 + 32 + (4 + 100) + 32 + 1, 0);
+    bank.set(
+        profile,
+        AccountState {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: todo_app::ID,
+            executable: false,
+        },
+    );
+
+    for (index, content) in todos.iter().enumerate() {
+        let Some(content) = content else {
+            continue;
+        };
+
+        let mut data = Todo::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(profile.as_ref());
+        content.to_string().serialize(&mut data).unwrap();
+        data.push(0); // completed
+        bank.set(
+            todo_address(&profile, index as u32),
+            AccountState {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: todo_app::ID,
+                executable: false,
+            },
+        );
+    }
+
+    profile
+}
+
+fn migrate_profile(
+    bank: &mut Bank,
+    user: Pubkey,
+    indices: std::ops::Range<u32>,
+) -> std::result::Result<(), ProgramError> {
+    let profile = profile_address(&user);
+    let mut metas = accounts::MigrateProfile {
+        user,
+        profile,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    metas.extend(
+        indices.map(|index| AccountMeta::new_readonly(todo_address(&profile, index), false)),
+    );
+
+    bank.process_instruction(Instruction {
+        program_id: todo_app::ID,
+        accounts: metas,
+        data: instruction::MigrateProfile {}.data(),
+    })
+}
+
+impl Fixture {
+    fn create_todo(
+        &mut self,
+        creator: Pubkey,
+        content: &str,
+    ) -> std::result::Result<Pubkey, ProgramError> {
+        let list: TodoList = self.bank.account(&self.list);
+        let todo = todo_address(&self.list, list.todo_count);
+
+        self.bank.process(
+            accounts::CreateTodo {
+                creator,
+                profile: self.profile,
+                list: self.list,
+                todo,
+                system_program: system_program::ID,
+                tag_index: None,
+                collaborator: None,
+                session_key: None,
+            },
+            instruction::CreateTodo {
+                content: content.to_string(),
+                due_at: None,
+                priority: Priority::Medium,
+                tags: Vec::new(),
+            },
+        )?;
+
+        Ok(todo)
+    }
+
+    fn toggle_todo(&mut self, user: Pubkey, todo: Pubkey) -> std::result::Result<(), ProgramError> {
+        self.bank.process(
+            accounts::ToggleTodo {
+                user,
+                profile: self.profile,
+                todo,
+                collaborator: None,
+                session_key: None,
+                list: None,
+                system_program: None,
+            },
+            instruction::ToggleTodo {
+                allow_overdue: false,
+            },
+        )
+    }
+
+    fn delete_todo(&mut self, user: Pubkey, todo: Pubkey) -> std::result::Result<(), ProgramError> {
+        self.bank.process(
+            accounts::DeleteTodo {
+                user,
+                profile: self.profile,
+                authority: self.user,
+                todo,
+                // Todos created before lists existed have none
+                list: (self.list != Pubkey::default()).then_some(self.list),
+                tag_index: None,
+                collaborator: None,
+            },
+            instruction::DeleteTodo {},
+        )
+    }
+
+    fn schedule_daily(&mut self, todo: Pubkey, due_at: i64) {
+        self.bank
+            .process(
+                accounts::UpdateTodoSchedule {
+                    user: self.user,
+                    profile: self.profile,
+                    todo,
+                    collaborator: None,
+                },
+                instruction::UpdateTodoSchedule {
+                    due_at: Some(due_at),
+                    priority: Priority::Medium,
+                    recurrence: Some(Recurrence::Daily),
+                },
+            )
+            .unwrap();
+    }
+
+    /// Completes a recurring todo, spawning the next occurrence at the end of
+    /// the list.
+    fn complete_recurring(&mut self, todo: Pubkey) -> std::result::Result<Pubkey, ProgramError> {
+        let list: TodoList = self.bank.account(&self.list);
+        let next = todo_address(&self.list, list.todo_count);
+
+        let mut metas = accounts::ToggleTodo {
+            user: self.user,
+            profile: self.profile,
+            todo,
+            collaborator: None,
+            session_key: None,
+            list: Some(self.list),
+            system_program: Some(system_program::ID),
+        }
+        .to_account_metas(None);
+        metas.push(AccountMeta::new(next, false));
+
+        self.bank.process_instruction(Instruction {
+            program_id: todo_app::ID,
+            accounts: metas,
+            data: instruction::ToggleTodo {
+                allow_overdue: true,
+            }
+            .data(),
+        })?;
+
+        Ok(next)
+    }
+
+    fn create_subtask(&mut self, todo: Pubkey) -> std::result::Result<Pubkey, ProgramError> {
+        let account: Todo = self.bank.account(&todo);
+        let subtask = Pubkey::find_program_address(
+            &[
+                SUBTASK_SEED,
+                todo.as_ref(),
+                &account.subtask_count.to_le_bytes(),
+            ],
+            &todo_app::ID,
+        )
+        .0;
+
+        self.bank.process(
+            accounts::CreateSubtask {
+                user: self.user,
+                profile: self.profile,
+                todo,
+                subtask,
+                system_program: system_program::ID,
+                collaborator: None,
+            },
+            instruction::CreateSubtask {
+                content: "Step".to_string(),
+            },
+        )?;
+
+        Ok(subtask)
+    }
+
+    fn create_session_key(&mut self, signer: Pubkey, expires_at: i64) -> Pubkey {
+        let session_key = Pubkey::find_program_address(
+            &[SESSION_KEY_SEED, self.profile.as_ref(), signer.as_ref()],
+            &todo_app::ID,
+        )
+        .0;
+
+        self.bank
+            .process(
+                accounts::CreateSessionKey {
+                    user: self.user,
+                    profile: self.profile,
+                    session_key,
+                    system_program: system_program::ID,
+                },
+                instruction::CreateSessionKey {
+                    signer,
+                    expires_at,
+                    scope: SessionScope {
+                        create_todo: true,
+                        toggle_todo: true,
+                    },
+                },
+            )
+            .unwrap();
+
+        session_key
+    }
+
+    /// Allocated the way a client does with `SystemProgram.createAccount`.
+    fn create_todo_book(&mut self) -> Pubkey {
+        let book = Pubkey::new_unique();
+        let space = 8 + TodoBook::SPACE;
+        self.bank.set(
+            book,
+            AccountState {
+                lamports: Rent::default().minimum_balance(space),
+                data: vec![0; space],
+                owner: todo_app::ID,
+                executable: false,
+            },
+        );
+        self.bank
+            .process(
+                accounts::CreateTodoBook {
+                    user: self.user,
+                    profile: self.profile,
+                    book,
+                    collaborator: None,
+                },
+                instruction::CreateTodoBook {},
+            )
+            .unwrap();
+
+        book
+    }
+
+    /// Closes `accounts` along with the profile, once nothing else is left.
+    fn close_profile(&mut self, accounts: &[Pubkey]) -> std::result::Result<(), ProgramError> {
+        let mut metas = accounts::CloseProfile {
+            user: self.user,
+            profile: self.profile,
+        }
+        .to_account_metas(None);
+        metas.extend(
+            accounts
+                .iter()
+                .map(|account| AccountMeta::new(*account, false)),
+        );
+
+        self.bank.process_instruction(Instruction {
+            program_id: todo_app::ID,
+            accounts: metas,
+            data: instruction::CloseProfile {}.data(),
+        })
+    }
+
+    fn create_archive(&mut self) -> Pubkey {
+        let profile: Profile = self.bank.account(&self.profile);
+        let archive = archive_address(&self.profile, profile.archive_count);
+        self.bank
+            .process(
+                accounts::CreateArchive {
+                    user: self.user,
+                    profile: self.profile,
+                    archive,
+                    system_program: system_program::ID,
+                    collaborator: None,
+                },
+                instruction::CreateArchive {},
+            )
+            .unwrap();
+
+        archive
+    }
+
+    fn archive_todo(
+        &mut self,
+        archive: Pubkey,
+        index: u32,
+    ) -> std::result::Result<(), ProgramError> {
+        self.bank.process(
+            accounts::ArchiveTodo {
+                user: self.user,
+                profile: self.profile,
+                authority: self.user,
+                todo: todo_address(&self.list, index),
+                archive,
+                list: Some(self.list),
+                tag_index: None,
+                system_program: system_program::ID,
+                collaborator: None,
+            },
+            instruction::ArchiveTodo { index },
+        )
+    }
+    fn add_comment(&mut self, author: Pubkey, todo: Pubkey) -> Pubkey {
+        let account: Todo = self.bank.account(&todo);
+        let comment = Pubkey::find_program_address(
+            &[
+                COMMENT_SEED,
+                todo.as_ref(),
+                &account.comment_count.to_le_bytes(),
+            ],
+            &todo_app::ID,
+        )
+        .0;
+
+        self.bank
+            .process(
+                accounts::AddComment {
+                    author,
+                    profile: self.profile,
+                    todo,
+                    comment,
+                    system_program: system_program::ID,
+                    collaborator: None,
+                },
+                instruction::AddComment {
+                    body: "Looks good".to_string(),
+                },
+            )
+            .unwrap();
+
+        comment
+    }
+
+    /// Closes `(comment, author)` pairs of `todo`.
+    fn delete_comments(
+        &mut self,
+        todo: Pubkey,
+        pairs: &[AccountMeta],
+    ) -> std::result::Result<(), ProgramError> {
+        let mut metas = accounts::DeleteComments {
+            user: self.user,
+            profile: self.profile,
+            todo,
+            collaborator: None,
+        }
+        .to_account_metas(None);
+        metas.extend_from_slice(pairs);
+
+        self.bank.process_instruction(Instruction {
+            program_id: todo_app::ID,
+            accounts: metas,
+            data: instruction::DeleteComments {}.data(),
+        })
+    }
+}
+
+#[test]
+fn create_profile_initializes_profile() {
+    let mut bank = Bank::new();
+    let user = bank.wallet(AIRDROP);
+
+    create_profile(&mut bank, user, "Alice").unwrap();
+
+    let key = profile_address(&user);
+    let profile: Profile = bank.account(&key);
+    assert_eq!(profile.key, key);
+    assert_eq!(profile.name, "Alice");
+    assert_eq!(profile.authority, user);
+    assert_eq!(profile.version, Profile::VERSION);
+    assert_eq!(profile.todo_count, 0);
+    assert_eq!(profile.list_count, 0);
+    assert_eq!(profile.open_count, 0);
+    assert_eq!(bank.get(&key).unwrap().owner, todo_app::ID);
+}
+
+#[test]
+fn create_profile_rejects_long_name() {
+    let mut bank = Bank::new();
+    let user = bank.wallet(AIRDROP);
+
+    let result = create_profile(&mut bank, user, &"a".repeat(101));
+
+    assert_eq!(result, Err(app_error(AppError::NameTooLong)));
+    assert!(bank.get(&profile_address(&user)).is_none());
+    assert_eq!(bank.lamports(&user), AIRDROP);
+}
+
+#[test]
+fn create_profile_accepts_name_at_limit() {
+    let mut bank = Bank::new();
+    let user = bank.wallet(AIRDROP);
+
+    create_profile(&mut bank, user, &"a".repeat(100)).unwrap();
+
+    let profile: Profile = bank.account(&profile_address(&user));
+    assert_eq!(profile.name.len(), 100);
+}
+
+#[test]
+fn create_todo_stores_content() {
+    let mut fixture = setup();
+
+    let todo = fixture.create_todo(fixture.user, "Buy milk").unwrap();
+
+    let account: Todo = fixture.bank.account(&todo);
+    assert_eq!(account.content, "Buy milk");
+    assert_eq!(account.profile, fixture.profile);
+    assert_eq!(account.list, fixture.list);
+    assert_eq!(account.created_at, UNIX_TIMESTAMP);
+    assert!(!account.completed);
+
+    let list: TodoList = fixture.bank.account(&fixture.list);
+    assert_eq!(list.todo_count, 1);
+    assert_eq!(list.active_todo_count, 1);
+
+    let profile: Profile = fixture.bank.account(&fixture.profile);
+    assert_eq!(profile.open_count, 1);
+
+    let second = fixture.create_todo(fixture.user, "Walk the dog").unwrap();
+    assert_eq!(second, todo_address(&fixture.list, 1));
+}
+
+#[test]
+fn create_todo_rejects_long_content() {
+    let mut fixture = setup();
+
+    let result = fixture.create_todo(fixture.user, &"a".repeat(201));
+
+    assert_eq!(result, Err(app_error(AppError::ContentTooLong)));
+    assert!(fixture.bank.get(&todo_address(&fixture.list, 0)).is_none());
+    let list: TodoList = fixture.bank.account(&fixture.list);
+    assert_eq!(list.todo_count, 0);
+}
+
+#[test]
+fn create_todo_rejects_other_signer() {
+    let mut fixture = setup();
+    let stranger = fixture.bank.wallet(AIRDROP);
+
+    let result = fixture.create_todo(stranger, "Buy milk");
+
+    assert_eq!(result, Err(app_error(AppError::InvalidAuthority)));
+}
+
+#[test]
+fn toggle_todo_completes_and_reopens() {
+    let mut fixture = setup();
+    let todo = fixture.create_todo(fixture.user, "Buy milk").unwrap();
+
+    fixture.toggle_todo(fixture.user, todo).unwrap();
+
+    let account: Todo = fixture.bank.account(&todo);
+    assert!(account.completed);
+    assert_eq!(account.completed_at, Some(UNIX_TIMESTAMP));
+    let profile: Profile = fixture.bank.account(&fixture.profile);
+    assert_eq!(profile.open_count, 0);
+    assert_eq!(profile.completed_count, 1);
+    assert_eq!(profile.current_streak, 1);
+
+    fixture.toggle_todo(fixture.user, todo).unwrap();
+
+    let account: Todo = fixture.bank.account(&todo);
+    assert!(!account.completed);
+    assert_eq!(account.completed_at, None);
+    let profile: Profile = fixture.bank.account(&fixture.profile);
+    assert_eq!(profile.open_count, 1);
+    assert_eq!(profile.completed_count, 0);
+}
+
+#[test]
+fn toggle_todo_rejects_other_signer() {
+    let mut fixture = setup();
+    let todo = fixture.create_todo(fixture.user, "Buy milk").unwrap();
+    let stranger = fixture.bank.wallet(AIRDROP);
+
+    let result = fixture.toggle_todo(stranger, todo);
+
+    assert_eq!(result, Err(app_error(AppError::InvalidAuthority)));
+    let account: Todo = fixture.bank.account(&todo);
+    assert!(!account.completed);
+}
+
+#[test]
+fn toggle_todo_rejects_todo_of_other_profile() {
+    let mut fixture = setup();
+    let mut other = setup();
+    let todo = other.create_todo(other.user, "Buy milk").unwrap();
+    // Move the other profile's todo into this bank
+    let state = other.bank.get(&todo).unwrap().clone();
+    fixture.bank.set(todo, state);
+
+    let result = fixture.toggle_todo(fixture.user, todo);
+
+    assert_eq!(result, Err(app_error(AppError::InvalidAuthority)));
+}
+
+#[test]
+fn delete_todo_refunds_rent() {
+    let mut fixture = setup();
+    let todo = fixture.create_todo(fixture.user, "Buy milk").unwrap();
+    let rent = fixture.bank.lamports(&todo);
+    let balance = fixture.bank.lamports(&fixture.user);
+    assert!(rent > 0);
+
+    fixture.delete_todo(fixture.user, todo).unwrap();
+
+    assert!(fixture.bank.get(&todo).is_none());
+    assert_eq!(fixture.bank.lamports(&fixture.user), balance + rent);
+
+    let list: TodoList = fixture.bank.account(&fixture.list);
+    assert_eq!(list.todo_count, 1);
+    assert_eq!(list.active_todo_count, 0);
+    let profile: Profile = fixture.bank.account(&fixture.profile);
+    assert_eq!(profile.open_count, 0);
+    assert_eq!(profile.deleted_count, 1);
+}
+
+#[test]
+fn delete_todo_rejects_other_signer() {
+    let mut fixture = setup();
+    let todo = fixture.create_todo(fixture.user, "Buy milk").unwrap();
+    let stranger = fixture.bank.wallet(AIRDROP);
+    let rent = fixture.bank.lamports(&todo);
+
+    let result = fixture.delete_todo(stranger, todo);
+
+    assert_eq!(result, Err(app_error(AppError::InvalidAuthority)));
+    assert_eq!(fixture.bank.lamports(&todo), rent);
+    assert_eq!(fixture.bank.lamports(&stranger), AIRDROP);
+}
+
+#[test]
+fn migrate_profile_counts_legacy_todos_in_batches() {
+    let mut bank = Bank::new();
+    let user = bank.wallet(AIRDROP);
+    let profile = legacy_profile(&mut bank, user, &[Some("a"), None, Some("b"), Some("c")]);
+
+    assert_eq!(
+        migrate_profile(&mut bank, user, 0..5),
+        Err(app_error(AppError::TodoAccountsMismatch))
+    );
+
+    migrate_profile(&mut bank, user, 0..2).unwrap();
+
+    let account: Profile = bank.account(&profile);
+    assert_eq!(account.version, 0);
+    assert_eq!(account.legacy_todos_checked, 2);
+    assert_eq!(account.active_todo_count, 1);
+
+    // Batches continue where the last one stopped
+    assert_eq!(
+        migrate_profile(&mut bank, user, 0..2),
+        Err(app_error(AppError::InvalidTodo))
+    );
+
+    migrate_profile(&mut bank, user, 2..4).unwrap();
+
+    let account: Profile = bank.account(&profile);
+    assert_eq!(account.version, Profile::VERSION);
+    assert_eq!(account.todo_count, 4);
+    assert_eq!(account.active_todo_count, 3);
+    assert_eq!(account.name, "Alice");
+    assert_eq!(account.authority, user);
+}
+
+#[test]
+fn migrate_todo_grows_legacy_todo() {
+    let mut bank = Bank::new();
+    let user = bank.wallet(AIRDROP);
+    let content = "a".repeat(150);
+    let profile = legacy_profile(&mut bank, user, &[Some(&content)]);
+    migrate_profile(&mut bank, user, 0..1).unwrap();
+    let todo = todo_address(&profile, 0);
+    let mut fixture = Fixture {
+        bank,
+        user,
+        profile,
+        list: Pubkey::default(),
+    };
+
+    // Too short for the fields appended since
+    assert_eq!(
+        fixture.toggle_todo(user, todo),
+        Err(ProgramError::Custom(
+            ErrorCode::AccountDidNotDeserialize.into()
+        ))
+    );
+
+    let migrate = |fixture: &mut Fixture, user: Pubkey| {
+        fixture.bank.process(
+            accounts::MigrateTodo {
+                user,
+                profile,
+                todo,
+                system_program: system_program::ID,
+                collaborator: None,
+            },
+            instruction::MigrateTodo {},
+        )
+    };
+    let stranger = fixture.bank.wallet(AIRDROP);
+    assert_eq!(
+        migrate(&mut fixture, stranger),
+        Err(app_error(AppError::InvalidAuthority))
+    );
+    migrate(&mut fixture, user).unwrap();
+
+    let account: Todo = fixture.bank.account(&todo);
+    assert_eq!(account.content, content);
+    assert_eq!(account.list, Pubkey::default());
+
+    fixture.toggle_todo(user, todo).unwrap();
+    let rent = fixture.bank.lamports(&todo);
+    let balance = fixture.bank.lamports(&user);
+    fixture.delete_todo(user, todo).unwrap();
+
+    assert!(fixture.bank.get(&todo).is_none());
+    assert_eq!(fixture.bank.lamports(&user), balance + rent);
+    let account: Profile = fixture.bank.account(&profile);
+    assert_eq!(account.active_todo_count, 0);
+}
+
+#[test]
+fn update_todo_refunds_shrunk_rent_to_owner() {
+    let mut fixture = setup();
+    let todo = fixture.create_todo(fixture.user, &"a".repeat(200)).unwrap();
+    let editor = fixture.bank.wallet(AIRDROP);
+    let collaborator = Pubkey::find_program_address(
+        &[COLLABORATOR_SEED, fixture.profile.as_ref(), editor.as_ref()],
+        &todo_app::ID,
+    )
+    .0;
+    fixture
+        .bank
+        .process(
+            accounts::AddCollaborator {
+                user: fixture.user,
+                profile: fixture.profile,
+                collaborator,
+                system_program: system_program::ID,
+            },
+            instruction::AddCollaborator {
+                member: editor,
+                role: Role::Editor,
+            },
+        )
+        .unwrap();
+    let rent = fixture.bank.lamports(&todo);
+    let balance = fixture.bank.lamports(&fixture.user);
+
+    let mut update = |authority: Pubkey| {
+        fixture.bank.process(
+            accounts::UpdateTodo {
+                user: editor,
+                profile: fixture.profile,
+                authority,
+                todo,
+                system_program: system_program::ID,
+                collaborator: Some(collaborator),
+            },
+            instruction::UpdateTodo {
+                content: String::new(),
+            },
+        )
+    };
+    assert_eq!(update(editor), Err(app_error(AppError::InvalidAuthority)));
+    update(fixture.user).unwrap();
+
+    let account: Todo = fixture.bank.account(&todo);
+    assert_eq!(account.content, "");
+    let refund = rent - fixture.bank.lamports(&todo);
+    assert!(refund > 0);
+    assert_eq!(fixture.bank.lamports(&fixture.user), balance + refund);
+    assert_eq!(fixture.bank.lamports(&editor), AIRDROP);
+}
+
+#[test]
+fn cancel_bounty_after_unassigning() {
+    let mut fixture = setup();
+    let todo = todo_address(&fixture.list, 0);
+    let bounty = Pubkey::find_program_address(&[BOUNTY_SEED, todo.as_ref()], &todo_app::ID).0;
+    let reviewer = Pubkey::new_unique();
+    fixture
+        .bank
+        .process(
+            accounts::CreateBountyTodo {
+                creator: fixture.user,
+                profile: fixture.profile,
+                list: fixture.list,
+                todo,
+                bounty,
+                system_program: system_program::ID,
+                collaborator: None,
+                mint: None,
+                creator_token_account: None,
+                vault: None,
+                token_program: None,
+                associated_token_program: None,
+            },
+            instruction::CreateBountyTodo {
+                content: "Fix the fence".to_string(),
+                amount: 1_000_000,
+                reviewer,
+            },
+        )
+        .unwrap();
+
+    let assign = |fixture: &mut Fixture, assignee: Pubkey| {
+        fixture.bank.process(
+            accounts::AssignBounty {
+                creator: fixture.user,
+                bounty,
+                assignee,
+            },
+            instruction::AssignBounty {},
+        )
+    };
+    // Program-owned accounts could never be paid out
+    let profile = fixture.profile;
+    assert_eq!(
+        assign(&mut fixture, profile),
+        Err(ProgramError::Custom(
+            ErrorCode::AccountNotSystemOwned.into()
+        ))
+    );
+    let assignee = fixture.bank.wallet(AIRDROP);
+    assign(&mut fixture, assignee).unwrap();
+
+    let cancel = |fixture: &mut Fixture| {
+        fixture.bank.process(
+            accounts::CancelBounty {
+                creator: fixture.user,
+                bounty,
+                todo,
+                mint: None,
+                vault: None,
+                creator_token_account: None,
+                token_program: None,
+            },
+            instruction::CancelBounty {},
+        )
+    };
+    assert_eq!(
+        cancel(&mut fixture),
+        Err(app_error(AppError::BountyAssigned))
+    );
+
+    fixture
+        .bank
+        .process(
+            accounts::UnassignBounty {
+                creator: fixture.user,
+                bounty,
+            },
+            instruction::UnassignBounty {},
+        )
+        .unwrap();
+    let account: Bounty = fixture.bank.account(&bounty);
+    assert_eq!(account.assignee, None);
+
+    let escrow = fixture.bank.lamports(&bounty);
+    let balance = fixture.bank.lamports(&fixture.user);
+    cancel(&mut fixture).unwrap();
+
+    assert!(fixture.bank.get(&bounty).is_none());
+    assert_eq!(fixture.bank.lamports(&fixture.user), balance + escrow);
+    let account: Todo = fixture.bank.account(&todo);
+    assert_eq!(account.bounty, None);
+}
+
+#[test]
+fn forfeit_commitment_pays_program_owned_beneficiary() {
+    let mut fixture = setup();
+    let todo = fixture.create_todo(fixture.user, "Run 5k").unwrap();
+    let commitment =
+        Pubkey::find_program_address(&[COMMITMENT_SEED, todo.as_ref()], &todo_app::ID).0;
+    // Any program-owned account, e.g. a treasury PDA
+    let beneficiary = fixture.list;
+    let deadline = UNIX_TIMESTAMP + 60;
+    let (user, profile) = (fixture.user, fixture.profile);
+
+    let mut create = |amount: u64| {
+        fixture.bank.process(
+            accounts::CreateCommitment {
+                user,
+                profile,
+                todo,
+                commitment,
+                system_program: system_program::ID,
+            },
+            instruction::CreateCommitment {
+                amount,
+                deadline,
+                beneficiary,
+            },
+        )
+    };
+    assert_eq!(create(1), Err(app_error(AppError::InvalidCommitment)));
+    let amount = Rent::default().minimum_balance(0);
+    create(amount).unwrap();
+
+    fixture.bank.warp(deadline + 1);
+    let balance = fixture.bank.lamports(&beneficiary);
+    fixture
+        .bank
+        .process(
+            accounts::ForfeitCommitment {
+                caller: user,
+                commitment,
+                todo,
+                staker: user,
+                beneficiary,
+            },
+            instruction::ForfeitCommitment {},
+        )
+        .unwrap();
+
+    assert_eq!(fixture.bank.lamports(&beneficiary), balance + amount);
+    let account: Todo = fixture.bank.account(&todo);
+    assert_eq!(account.commitment, None);
+}
+
+#[test]
+fn close_todo_book_drops_its_entries_from_profile_counts() {
+    let mut fixture = setup();
+    let (user, profile) = (fixture.user, fixture.profile);
+    let book = fixture.create_todo_book();
+
+    let update = || accounts::UpdateTodoBook {
+        user,
+        profile,
+        book,
+        collaborator: None,
+    };
+    for content in ["Buy milk", "Walk dog"] {
+        fixture
+            .bank
+            .process(
+                update(),
+                instruction::AddBookTodo {
+                    content: content.to_string(),
+                    priority: Priority::Low,
+                },
+            )
+            .unwrap();
+    }
+    fixture
+        .bank
+        .process(update(), instruction::ToggleBookTodo { slot: 0 })
+        .unwrap();
+    let account: Profile = fixture.bank.account(&profile);
+    assert_eq!((account.open_count, account.completed_count), (1, 1));
+
+    fixture
+        .bank
+        .process(
+            accounts::CloseTodoBook {
+                user,
+                profile,
+                authority: user,
+                book,
+                collaborator: None,
+            },
+            instruction::CloseTodoBook {},
+        )
+        .unwrap();
+
+    assert!(fixture.bank.get(&book).is_none());
+    let account: Profile = fixture.bank.account(&profile);
+    assert_eq!((account.open_count, account.completed_count), (0, 0));
+}
+
+#[test]
+fn claim_handle_rejects_overlong_name() {
+    let mut fixture = setup();
+    let (user, profile) = (fixture.user, fixture.profile);
+
+    let mut claim = |name: &str| {
+        let handle =
+            Pubkey::find_program_address(&[HANDLE_SEED, &Handle::seed(name)], &todo_app::ID).0;
+        fixture.bank.process(
+            accounts::ClaimHandle {
+                user,
+                profile,
+                handle,
+                system_program: system_program::ID,
+            },
+            instruction::ClaimHandle {
+                name: name.to_string(),
+            },
+        )
+    };
+
+    assert_eq!(
+        claim(&"a".repeat(33)),
+        Err(app_error(AppError::InvalidHandle))
+    );
+    claim(&"a".repeat(32)).unwrap();
+}
+
+#[test]
+fn close_profile_releases_subtasks_before_their_todo() {
+    let mut fixture = setup();
+    let todo = fixture.create_todo(fixture.user, "Move house").unwrap();
+    let subtask = fixture.create_subtask(todo).unwrap();
+    let (profile, list) = (fixture.profile, fixture.list);
+
+    assert_eq!(
+        fixture.close_profile(&[todo, list]),
+        Err(app_error(AppError::TodoHasSubtasks))
+    );
+    assert_eq!(
+        fixture.close_profile(&[todo, subtask, list]),
+        Err(app_error(AppError::TodoHasSubtasks))
+    );
+
+    fixture.close_profile(&[subtask, todo, list]).unwrap();
+
+    for account in [subtask, todo, list, profile] {
+        assert!(fixture.bank.get(&account).is_none());
+    }
+}
+
+#[test]
+fn create_subtask_rejects_completed_todo() {
+    let mut fixture = setup();
+    let todo = fixture.create_todo(fixture.user, "Move house").unwrap();
+    fixture.toggle_todo(fixture.user, todo).unwrap();
+
+    assert_eq!(
+        fixture.create_subtask(todo),
+        Err(app_error(AppError::InvalidTodo))
+    );
+
+    fixture.toggle_todo(fixture.user, todo).unwrap();
+    fixture.create_subtask(todo).unwrap();
+}
+
+#[test]
+fn close_profile_waits_for_tag_index() {
+    let mut fixture = setup();
+    let (user, profile, list) = (fixture.user, fixture.profile, fixture.list);
+    let tag_index =
+        Pubkey::find_program_address(&[TAG_INDEX_SEED, profile.as_ref()], &todo_app::ID).0;
+    fixture
+        .bank
+        .process(
+            accounts::CreateTagIndex {
+                user,
+                profile,
+                tag_index,
+                system_program: system_program::ID,
+                collaborator: None,
+            },
+            instruction::CreateTagIndex {},
+        )
+        .unwrap();
+
+    fixture.close_profile(&[list]).unwrap();
+    let account: Profile = fixture.bank.account(&profile);
+    assert!(account.has_tag_index);
+
+    fixture.close_profile(&[tag_index]).unwrap();
+    assert!(fixture.bank.get(&tag_index).is_none());
+    assert!(fixture.bank.get(&profile).is_none());
+}
+
+#[test]
+fn close_profile_waits_for_todo_books() {
+    let mut fixture = setup();
+    let (profile, list) = (fixture.profile, fixture.list);
+    let book = fixture.create_todo_book();
+    let account: Profile = fixture.bank.account(&profile);
+    assert_eq!(account.todo_book_count, 1);
+
+    fixture.close_profile(&[list]).unwrap();
+    assert!(fixture.bank.get(&profile).is_some());
+
+    fixture.close_profile(&[book]).unwrap();
+    assert!(fixture.bank.get(&book).is_none());
+    assert!(fixture.bank.get(&profile).is_none());
+}
+
+#[test]
+fn set_prerequisites_checks_indices_and_cycles() {
+    let mut fixture = setup();
+    let first = fixture.create_todo(fixture.user, "Pack").unwrap();
+    let second = fixture.create_todo(fixture.user, "Move").unwrap();
+    let (user, profile, list) = (fixture.user, fixture.profile, fixture.list);
+
+    let mut set = |todo: Pubkey, index: u32, prerequisites: Vec<u32>, reachable: &[Pubkey]| {
+        let mut metas = accounts::SetPrerequisites {
+            user,
+            profile,
+            todo,
+            list: Some(list),
+            collaborator: None,
+        }
+        .to_account_metas(None);
+        metas.extend(
+            reachable
+                .iter()
+                .map(|todo| AccountMeta::new_readonly(*todo, false)),
+        );
+
+        fixture.bank.process_instruction(Instruction {
+            program_id: todo_app::ID,
+            accounts: metas,
+            data: instruction::SetPrerequisites {
+                index,
+                prerequisites,
+            }
+            .data(),
+        })
+    };
+
+    assert_eq!(
+        set(second, 1, vec![2], &[]),
+        Err(app_error(AppError::UnknownPrerequisite))
+    );
+    assert_eq!(
+        set(second, 1, vec![0], &[]),
+        Err(app_error(AppError::MissingPrerequisites))
+    );
+    set(second, 1, vec![0], &[first]).unwrap();
+    assert_eq!(
+        set(first, 0, vec![1], &[second]),
+        Err(app_error(AppError::DependencyCycle))
+    );
+
+    let account: Todo = fixture.bank.account(&second);
+    assert_eq!(account.prerequisites, vec![0]);
+}
+
+#[test]
+fn close_profile_waits_for_session_keys() {
+    let mut fixture = setup();
+    let (profile, list) = (fixture.profile, fixture.list);
+    let session_key = fixture.create_session_key(Pubkey::new_unique(), UNIX_TIMESTAMP + 60);
+
+    fixture.close_profile(&[list]).unwrap();
+    let account: Profile = fixture.bank.account(&profile);
+    assert_eq!(account.session_key_count, 1);
+
+    fixture.close_profile(&[session_key]).unwrap();
+    assert!(fixture.bank.get(&session_key).is_none());
+    assert!(fixture.bank.get(&profile).is_none());
+}
+
+#[test]
+fn close_profile_waits_for_integrations() {
+    let mut fixture = setup();
+    let (user, profile, list) = (fixture.user, fixture.profile, fixture.list);
+    let program = Pubkey::new_unique();
+    let integration = Pubkey::find_program_address(
+        &[INTEGRATION_SEED, profile.as_ref(), program.as_ref()],
+        &todo_app::ID,
+    )
+    .0;
+    fixture
+        .bank
+        .process(
+            accounts::ApproveIntegration {
+                user,
+                profile,
+                integration,
+                system_program: system_program::ID,
+            },
+            instruction::ApproveIntegration { program },
+        )
+        .unwrap();
+
+    fixture.close_profile(&[list]).unwrap();
+    let account: Profile = fixture.bank.account(&profile);
+    assert_eq!(account.integration_count, 1);
+
+    fixture.close_profile(&[integration]).unwrap();
+    assert!(fixture.bank.get(&integration).is_none());
+    assert!(fixture.bank.get(&profile).is_none());
+}
+
+#[test]
+fn close_profile_closes_archive_pages_from_the_latest() {
+    let mut fixture = setup();
+    let (profile, list) = (fixture.profile, fixture.list);
+    let pages = [fixture.create_archive(), fixture.create_archive()];
+
+    assert_eq!(
+        fixture.close_profile(&[list, pages[0], pages[1]]),
+        Err(app_error(AppError::InvalidArchive))
+    );
+    fixture.close_profile(&[list, pages[1]]).unwrap();
+    let account: Profile = fixture.bank.account(&profile);
+    assert_eq!(account.archive_count, 1);
+
+    fixture.close_profile(&[pages[0]]).unwrap();
+    assert!(fixture.bank.get(&pages[0]).is_none());
+    assert!(fixture.bank.get(&profile).is_none());
+}
+
+#[test]
+fn completing_recurring_todo_spawns_next_occurrence() {
+    let mut fixture = setup();
+    let todo = fixture.create_todo(fixture.user, "Water plants").unwrap();
+    // Completed two and a half days late
+    let due_at = UNIX_TIMESTAMP - 60 * 60 * 60;
+    fixture.schedule_daily(todo, due_at);
+
+    let next = fixture.complete_recurring(todo).unwrap();
+
+    let account: Todo = fixture.bank.account(&todo);
+    assert_eq!(account.next_occurrence, Some(next));
+    let account: Todo = fixture.bank.account(&next);
+    assert_eq!(account.content, "Water plants");
+    assert!(!account.completed);
+    assert_eq!(account.due_at, Some(due_at + 3 * 24 * 60 * 60));
+    assert!(matches!(account.recurrence, Some(Recurrence::Daily)));
+}
+
+#[test]
+fn completing_recurring_todo_rejects_unrepresentable_due_date() {
+    let mut fixture = setup();
+    let todo = fixture.create_todo(fixture.user, "Water plants").unwrap();
+    fixture.schedule_daily(todo, i64::MIN);
+
+    assert_eq!(
+        fixture.complete_recurring(todo),
+        Err(app_error(AppError::InvalidRecurrence))
+    );
+}
+
+#[test]
+fn session_key_signs_until_it_expires() {
+    let mut fixture = setup();
+    let signer = fixture.bank.wallet(AIRDROP);
+    let expires_at = UNIX_TIMESTAMP + 60 * 60;
+    let session_key = fixture.create_session_key(signer, expires_at);
+
+    let create = |fixture: &mut Fixture, creator: Pubkey| {
+        let list: TodoList = fixture.bank.account(&fixture.list);
+        let todo = todo_address(&fixture.list, list.todo_count);
+        fixture
+            .bank
+            .process(
+                accounts::CreateTodo {
+                    creator,
+                    profile: fixture.profile,
+                    list: fixture.list,
+                    todo,
+                    system_program: system_program::ID,
+                    tag_index: None,
+                    collaborator: None,
+                    session_key: Some(session_key),
+                },
+                instruction::CreateTodo {
+                    content: "From my phone".to_string(),
+                    due_at: None,
+                    priority: Priority::Medium,
+                    tags: Vec::new(),
+                },
+            )
+            .map(|()| todo)
+    };
+    let toggle = |fixture: &mut Fixture, todo: Pubkey| {
+        fixture.bank.process(
+            accounts::ToggleTodo {
+                user: signer,
+                profile: fixture.profile,
+                todo,
+                collaborator: None,
+                session_key: Some(session_key),
+                list: None,
+                system_program: None,
+            },
+            instruction::ToggleTodo {
+                allow_overdue: false,
+            },
+        )
+    };
+
+    // Only the signer the session was issued to can use it
+    let stranger = fixture.bank.wallet(AIRDROP);
+    assert_eq!(
+        create(&mut fixture, stranger),
+        Err(app_error(AppError::InvalidAuthority))
+    );
+
+    let todo = create(&mut fixture, signer).unwrap();
+    toggle(&mut fixture, todo).unwrap();
+    let account: Todo = fixture.bank.account(&todo);
+    assert!(account.completed);
+
+    fixture.bank.warp(expires_at);
+    assert_eq!(
+        create(&mut fixture, signer),
+        Err(app_error(AppError::InvalidAuthority))
+    );
+    assert_eq!(
+        toggle(&mut fixture, todo),
+        Err(app_error(AppError::InvalidAuthority))
+    );
+}
+
+#[test]
+fn archive_todo_grows_page_a_chunk_at_a_time() {
+    let mut fixture = setup();
+    let archive = fixture.create_archive();
+    let user = fixture.user;
+
+    let open = fixture.create_todo(user, "Still open").unwrap();
+    assert_eq!(
+        fixture.archive_todo(archive, 0),
+        Err(app_error(AppError::TodoNotCompleted))
+    );
+    fixture.toggle_todo(user, open).unwrap();
+
+    for _ in 0..Archive::CHUNK_LEN {
+        let todo = fixture.create_todo(user, "Done").unwrap();
+        fixture.toggle_todo(user, todo).unwrap();
+    }
+    let size = |fixture: &Fixture| fixture.bank.get(&archive).unwrap().data.len();
+
+    for index in 0..Archive::CHUNK_LEN as u32 {
+        fixture.archive_todo(archive, index).unwrap();
+        assert_eq!(size(&fixture), 8 + Archive::space(Archive::CHUNK_LEN));
+    }
+    fixture
+        .archive_todo(archive, Archive::CHUNK_LEN as u32)
+        .unwrap();
+    assert_eq!(size(&fixture), 8 + Archive::space(2 * Archive::CHUNK_LEN));
+
+    let account: Archive = fixture.bank.account(&archive);
+    assert_eq!(account.records.len(), Archive::CHUNK_LEN + 1);
+    assert_eq!(account.records[0].index, 0);
+    assert_eq!(
+        account.records[0].content_hash,
+        anchor_lang::solana_program::hash::hash(b"Still open").to_bytes()
+    );
+    let account: Profile = fixture.bank.account(&fixture.profile);
+    assert_eq!(account.active_todo_count, 0);
+}
+
+#[test]
+fn archive_todo_only_appends_to_latest_page_with_room() {
+    let mut fixture = setup();
+    let user = fixture.user;
+    let first = fixture.create_archive();
+    let latest = fixture.create_archive();
+    let todo = fixture.create_todo(user, "Done").unwrap();
+    fixture.toggle_todo(user, todo).unwrap();
+
+    assert_eq!(
+        fixture.archive_todo(first, 0),
+        Err(app_error(AppError::InvalidArchive))
+    );
+
+    // Fill the latest page up to its limit
+    let mut account: Archive = fixture.bank.account(&latest);
+    account.records = vec![
+        ArchiveRecord {
+            list: fixture.list,
+            index: 0,
+            content_hash: [0; 32],
+            completed_at: UNIX_TIMESTAMP,
+        };
+        Archive::MAX_RECORDS
+    ];
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    fixture.bank.set(
+        latest,
+        AccountState {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: todo_app::ID,
+            executable: false,
+        },
+    );
+
+    assert_eq!(
+        fixture.archive_todo(latest, 0),
+        Err(app_error(AppError::ArchiveFull))
+    );
+    assert!(fixture.bank.get(&todo).is_some());
+}
+
+#[test]
+fn delete_comments_refunds_each_author() {
+    let mut fixture = setup();
+    let user = fixture.user;
+    let todo = fixture.create_todo(user, "Paint the shed").unwrap();
+    let comments = [
+        fixture.add_comment(user, todo),
+        fixture.add_comment(user, todo),
+    ];
+    let rent: u64 = comments
+        .iter()
+        .map(|comment| fixture.bank.lamports(comment))
+        .sum();
+
+    assert_eq!(
+        fixture.delete_todo(user, todo),
+        Err(app_error(AppError::TodoHasComments))
+    );
+
+    let balance = fixture.bank.lamports(&user);
+    let pairs: Vec<AccountMeta> = comments
+        .iter()
+        .flat_map(|comment| {
+            [
+                AccountMeta::new(*comment, false),
+                AccountMeta::new(user, false),
+            ]
+        })
+        .collect();
+    fixture.delete_comments(todo, &pairs).unwrap();
+
+    assert!(comments
+        .iter()
+        .all(|comment| fixture.bank.get(comment).is_none()));
+    assert_eq!(fixture.bank.lamports(&user), balance + rent);
+    let account: Todo = fixture.bank.account(&todo);
+    assert_eq!(account.active_comment_count, 0);
+    fixture.delete_todo(user, todo).unwrap();
+}
+
+#[test]
+fn delete_comments_rejects_mismatched_pairs() {
+    let mut fixture = setup();
+    let user = fixture.user;
+    let todo = fixture.create_todo(user, "Paint the shed").unwrap();
+    let other = fixture.create_todo(user, "Mow the lawn").unwrap();
+    let comment = fixture.add_comment(user, todo);
+    let elsewhere = fixture.add_comment(user, other);
+    let stranger = fixture.bank.wallet(AIRDROP);
+
+    assert_eq!(
+        fixture.delete_comments(todo, &[AccountMeta::new(comment, false)]),
+        Err(app_error(AppError::TodoAccountsMismatch))
+    );
+    assert_eq!(
+        fixture.delete_comments(
+            todo,
+            &[
+                AccountMeta::new(comment, false),
+                AccountMeta::new(stranger, false)
+            ]
+        ),
+        Err(app_error(AppError::InvalidComment))
+    );
+    assert_eq!(
+        fixture.delete_comments(
+            todo,
+            &[
+                AccountMeta::new(elsewhere, false),
+                AccountMeta::new(user, false)
+            ]
+        ),
+        Err(app_error(AppError::InvalidComment))
+    );
+    assert_eq!(
+        fixture.delete_comments(
+            todo,
+            &[
+                AccountMeta::new_readonly(comment, false),
+                AccountMeta::new(user, false)
+            ]
+        ),
+        Err(ProgramError::Custom(ErrorCode::ConstraintMut.into()))
+    );
+
+    assert!(fixture.bank.get(&comment).is_some());
+    let account: Todo = fixture.bank.account(&todo);
+    assert_eq!(account.active_comment_count, 1);
+}
+
+#[test]
+fn token_bounty_escrows_and_pays_out_tokens() {
+    let mut fixture = setup();
+    let (user, profile, list) = (fixture.user, fixture.profile, fixture.list);
+    let todo = todo_address(&list, 0);
+    let bounty = Pubkey::find_program_address(&[BOUNTY_SEED, todo.as_ref()], &todo_app::ID).0;
+    let reviewer = fixture.bank.wallet(AIRDROP);
+    let assignee = fixture.bank.wallet(AIRDROP);
+
+    let mint = fixture.bank.mint(user, 6);
+    let creator_tokens = fixture.bank.token_account(mint, user, 5_000_000);
+    let assignee_tokens = fixture.bank.token_account(mint, assignee, 0);
+    let vault = get_associated_token_address(&bounty, &mint);
+
+    let create = |fixture: &mut Fixture, creator_token_account: Option<Pubkey>| {
+        fixture.bank.process(
+            accounts::CreateBountyTodo {
+                creator: user,
+                profile,
+                list,
+                todo,
+                bounty,
+                system_program: system_program::ID,
+                collaborator: None,
+                mint: Some(mint),
+                creator_token_account,
+                vault: Some(vault),
+                token_program: Some(spl_token::ID),
+                associated_token_program: Some(spl_associated_token_account::ID),
+            },
+            instruction::CreateBountyTodo {
+                content: "Fix the fence".to_string(),
+                amount: 2_000_000,
+                reviewer,
+            },
+        )
+    };
+    assert_eq!(
+        create(&mut fixture, None),
+        Err(app_error(AppError::MissingTokenAccounts))
+    );
+    create(&mut fixture, Some(creator_tokens)).unwrap();
+
+    assert_eq!(fixture.bank.token_balance(&vault), 2_000_000);
+    assert_eq!(fixture.bank.token_balance(&creator_tokens), 3_000_000);
+    let account: Bounty = fixture.bank.account(&bounty);
+    assert_eq!(account.mint, Some(mint));
+
+    fixture
+        .bank
+        .process(
+            accounts::AssignBounty {
+                creator: user,
+                bounty,
+                assignee,
+            },
+            instruction::AssignBounty {},
+        )
+        .unwrap();
+    fixture
+        .bank
+        .process(
+            accounts::SubmitBounty {
+                assignee,
+                bounty,
+                todo,
+                profile,
+            },
+            instruction::SubmitBounty {},
+        )
+        .unwrap();
+
+    let approve = |fixture: &mut Fixture, assignee_token_account: Option<Pubkey>| {
+        fixture.bank.process(
+            accounts::ApproveBounty {
+                reviewer,
+                bounty,
+                todo,
+                creator: user,
+                assignee,
+                mint: Some(mint),
+                vault: Some(vault),
+                assignee_token_account,
+                token_program: Some(spl_token::ID),
+            },
+            instruction::ApproveBounty {},
+        )
+    };
+    assert_eq!(
+        approve(&mut fixture, None),
+        Err(app_error(AppError::MissingTokenAccounts))
+    );
+    // The reward can only go to the assignee's own token account
+    assert_eq!(
+        approve(&mut fixture, Some(creator_tokens)),
+        Err(ProgramError::Custom(ErrorCode::ConstraintTokenOwner.into()))
+    );
+
+    let rent = fixture.bank.lamports(&bounty) + fixture.bank.lamports(&vault);
+    let balance = fixture.bank.lamports(&user);
+    approve(&mut fixture, Some(assignee_tokens)).unwrap();
+
+    assert_eq!(fixture.bank.token_balance(&assignee_tokens), 2_000_000);
+    assert!(fixture.bank.get(&vault).is_none());
+    assert!(fixture.bank.get(&bounty).is_none());
+    assert_eq!(fixture.bank.lamports(&user), balance + rent);
+}
+
+#[test]
+fn token_bounty_rejects_reward_beyond_creator_balance() {
+    let mut fixture = setup();
+    let (user, profile, list) = (fixture.user, fixture.profile, fixture.list);
+    let todo = todo_address(&list, 0);
+    let bounty = Pubkey::find_program_address(&[BOUNTY_SEED, todo.as_ref()], &todo_app::ID).0;
+    let mint = fixture.bank.mint(user, 6);
+    let creator_tokens = fixture.bank.token_account(mint, user, 1_000_000);
+    let vault = get_associated_token_address(&bounty, &mint);
+
+    assert_eq!(
+        fixture.bank.process(
+            accounts::CreateBountyTodo {
+                creator: user,
+                profile,
+                list,
+                todo,
+                bounty,
+                system_program: system_program::ID,
+                collaborator: None,
+                mint: Some(mint),
+                creator_token_account: Some(creator_tokens),
+                vault: Some(vault),
+                token_program: Some(spl_token::ID),
+                associated_token_program: Some(spl_associated_token_account::ID),
+            },
+            instruction::CreateBountyTodo {
+                content: "Fix the fence".to_string(),
+                amount: 2_000_000,
+                reviewer: Pubkey::new_unique(),
+            },
+        ),
+        Err(TokenError::InsufficientFunds.into())
+    );
+    assert!(fixture.bank.get(&vault).is_none());
+    assert_eq!(fixture.bank.token_balance(&creator_tokens), 1_000_000);
+}
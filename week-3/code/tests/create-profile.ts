@@ -36,6 +36,22 @@ describe("todo-app", () => {
       provider.publicKey.toBase58()
     );
     expect(profileAccount.todoCount).to.equal(0);
+    expect(profileAccount.version).to.equal(10);
+    expect(profileAccount.activeTodoCount).to.equal(0);
+    expect(profileAccount.listCount).to.equal(0);
+    expect(profileAccount.handle).to.be.null;
+    expect(profileAccount.pendingAuthority).to.be.null;
+    expect(profileAccount.encryptionKey).to.be.null;
+    expect(profileAccount.keyVersion).to.equal(0);
+    expect(profileAccount.archiveCount).to.equal(0);
+    expect(profileAccount.openCount).to.equal(0);
+    expect(profileAccount.completedCount).to.equal(0);
+    expect(profileAccount.currentStreak).to.equal(0);
+    expect(profileAccount.legacyTodosChecked).to.equal(0);
+    expect(profileAccount.hasTagIndex).to.be.false;
+    expect(profileAccount.todoBookCount).to.equal(0);
+    expect(profileAccount.sessionKeyCount).to.equal(0);
+    expect(profileAccount.integrationCount).to.equal(0);
   });
 
   it("Create profile failed", async () => {
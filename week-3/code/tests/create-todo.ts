@@ -14,6 +14,7 @@ describe("todo-app", () => {
   const content = "Do Solana bootcamp homework";
 
   let profile: anchor.web3.PublicKey;
+  let list: anchor.web3.PublicKey;
 
 before(async () => {
   [profile] = anchor.web3.PublicKey.findProgramAddressSync(
@@ -48,22 +49,46 @@ before(async () => {
       }
     }
   }
+
+  const profileAccount = await program.account.profile.fetch(profile);
+  const listIndex = Buffer.alloc(4);
+  listIndex.writeUInt32LE(profileAccount.listCount);
+
+  [list] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("todo_list"), profile.toBytes(), listIndex],
+    program.programId
+  );
+
+  const tx = await program.methods
+    .createTodoList("Bootcamp")
+    .accounts({
+      creator: provider.publicKey,
+      profile,
+      list,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    })
+    .rpc();
+
+  console.log("Create todo list success", tx);
 });
 
   it("Create todo successfully", async () => {
+    let listAccount = await program.account.todoList.fetch(list);
+    const currentTodoCount = listAccount.todoCount;
     let profileAccount = await program.account.profile.fetch(profile);
-    const currentTodoCount = profileAccount.todoCount;
+    const currentOpenCount = profileAccount.openCount;
 
     const [todo] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("todo"), profile.toBytes(), Buffer.from([currentTodoCount])],
+      [Buffer.from("todo"), list.toBytes(), Buffer.from([currentTodoCount])],
       program.programId
     );
 
     const tx = await program.methods
-      .createTodo(content)
+      .createTodo(content, null, { medium: {} }, [])
       .accounts({
         creator: provider.publicKey,
         profile,
+        list,
         todo,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
@@ -75,16 +100,23 @@ before(async () => {
     expect(todoAccount.content).to.equal(content);
     expect(todoAccount.profile.toBase58()).to.equal(profile.toBase58());
     expect(todoAccount.completed).to.equal(false);
+    expect(todoAccount.list.toBase58()).to.equal(list.toBase58());
+    expect(todoAccount.dueAt).to.equal(null);
+    expect(todoAccount.priority).to.deep.equal({ medium: {} });
+    expect(todoAccount.completedAt).to.equal(null);
+
+    listAccount = await program.account.todoList.fetch(list);
+    expect(listAccount.todoCount).to.equal(currentTodoCount + 1);
 
     profileAccount = await program.account.profile.fetch(profile);
-    expect(profileAccount.todoCount).to.equal(currentTodoCount + 1);
+    expect(profileAccount.openCount).to.equal(currentOpenCount + 1);
   });
 
   it("Create todo failed", async () => {
     withErrorTest(async () => {
       try {
-        let profileAccount = await program.account.profile.fetch(profile);
-        const currentTodoCount = profileAccount.todoCount;
+        let listAccount = await program.account.todoList.fetch(list);
+        const currentTodoCount = listAccount.todoCount;
 
         const longContent = `
     Lorem Ipsum is simply dummy text of the printing and typesetting industry. Lorem Ipsum has been the industry's standard dummy text ever since the 1500s, when an unknown printer took a galley of type and scrambled it to make a type specimen book. It has survived not only five centuries, but also the leap into electronic typesetting, remaining essentially unchanged. It was popularised in the 1960s with the release of Letraset sheets containing Lorem Ipsum passages, and more recently with desktop publishing software like Aldus PageMaker including versions of Lorem Ipsum.
@@ -93,17 +125,18 @@ before(async () => {
         const [todo] = anchor.web3.PublicKey.findProgramAddressSync(
           [
             Buffer.from("todo"),
-            profile.toBytes(),
+            list.toBytes(),
             Buffer.from([currentTodoCount]),
           ],
           program.programId
         );
 
         const tx = await program.methods
-          .createTodo(longContent)
+          .createTodo(longContent, null, { medium: {} }, [])
           .accounts({
             creator: provider.publicKey,
             profile,
+            list,
             todo,
             systemProgram: anchor.web3.SystemProgram.programId,
           })
@@ -142,8 +175,8 @@ before(async () => {
 
     withErrorTest(async () => {
       try {
-        let profileAccount = await program.account.profile.fetch(profile);
-        const currentTodoCount = profileAccount.todoCount;
+        let listAccount = await program.account.todoList.fetch(list);
+        const currentTodoCount = listAccount.todoCount;
 
         const content = `
     Lorem Ipsum is simply dummy text of the printing and typesetting industry..
@@ -152,17 +185,18 @@ before(async () => {
         const [todo] = anchor.web3.PublicKey.findProgramAddressSync(
           [
             Buffer.from("todo"),
-            profile.toBytes(),
+            list.toBytes(),
             Buffer.from([currentTodoCount]),
           ],
           program.programId
         );
 
         const tx = await program.methods
-          .createTodo(content)
+          .createTodo(content, null, { medium: {} }, [])
           .accounts({
             creator: anotherPayer.publicKey,
             profile,
+            list,
             todo,
             systemProgram: anchor.web3.SystemProgram.programId,
           })